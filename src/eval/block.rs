@@ -1,159 +1,591 @@
-use super::{Command, NameSpace};
+use super::{parallel, Command, NameSpace, Proc, ShellError, Value};
 use crate::job::{SharedJobs, Status};
-use crate::parse::{Block as ParseBlk, SpecialStr};
+use crate::parse::{Block as ParseBlk, ForHead, Pattern, SpecialStr, Type};
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Block {
     Single(Command),
-    Multi(Vec<Self>),
-    If(SpecialStr, Box<Self>, Option<Box<Self>>),
-    Case(SpecialStr, Vec<(Vec<SpecialStr>, Self)>),
-    For(String, SpecialStr, Box<Self>),
-    While(SpecialStr, Box<Self>),
-    Proc(String, Box<Self>),
-    Break,
-    Continue,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum State {
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+    Multi(Vec<(usize, Self)>),
+    If(Vec<(SpecialStr, Self)>, Option<Box<Self>>),
+    Case(SpecialStr, Vec<(Vec<Pattern>, Option<SpecialStr>, Self)>),
+    For(Option<String>, ForHead, Box<Self>),
+    While(Option<String>, SpecialStr, Box<Self>),
+    Loop(Option<String>, Box<Self>),
+    Proc(String, Proc),
+    Break(Option<String>),
+    Continue(Option<String>),
+    Return(Option<SpecialStr>),
+    Try(
+        Box<Self>,
+        Option<(Option<String>, Box<Self>)>,
+        Option<Box<Self>>,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum State {
     Normal,
-    Breaked,
-    Continued,
+    Breaked(Option<String>),
+    Continued(Option<String>),
+    Returned(Option<Value>),
 }
 
 impl From<ParseBlk> for Block {
     fn from(block: ParseBlk) -> Self {
         match block {
-            ParseBlk::Multi(blocks) => Self::Multi(blocks.into_iter().map(Self::from).collect()),
+            ParseBlk::Multi(blocks) => Self::Multi(
+                blocks
+                    .into_iter()
+                    .map(|(line, block)| (line, Self::from(block)))
+                    .collect(),
+            ),
             ParseBlk::Single(cmd) => Self::Single(Command::from(cmd)),
-            ParseBlk::If(cond, first, second) => Self::If(
-                cond,
-                Box::new(Self::from(*first)),
-                second.map(|sec| Box::new(Self::from(*sec))),
+            ParseBlk::Not(block) => Self::Not(Box::new(Self::from(*block))),
+            ParseBlk::And(lhs, rhs) => {
+                Self::And(Box::new(Self::from(*lhs)), Box::new(Self::from(*rhs)))
+            }
+            ParseBlk::Or(lhs, rhs) => {
+                Self::Or(Box::new(Self::from(*lhs)), Box::new(Self::from(*rhs)))
+            }
+            ParseBlk::If(branches, otherwise) => Self::If(
+                branches
+                    .into_iter()
+                    .map(|(cond, block)| (cond, Self::from(block)))
+                    .collect(),
+                otherwise.map(|block| Box::new(Self::from(*block))),
             ),
             ParseBlk::Case(cond, blocks) => Self::Case(
                 cond,
                 blocks
                     .into_iter()
-                    .map(|(pats, block)| (pats, Self::from(block)))
+                    .map(|(pats, guard, block)| (pats, guard, Self::from(block)))
                     .collect(),
             ),
-            ParseBlk::For(c, iter, block) => Self::For(c, iter, Box::new(Self::from(*block))),
-            ParseBlk::While(cond, block) => Self::While(cond, Box::new(Self::from(*block))),
-            ParseBlk::Proc(name, block) => Self::Proc(name, Box::new(Self::from(*block))),
-            ParseBlk::Break => Self::Break,
-            ParseBlk::Continue => Self::Continue,
+            ParseBlk::For(label, head, block) => {
+                Self::For(label, head, Box::new(Self::from(*block)))
+            }
+            ParseBlk::While(label, cond, block) => {
+                Self::While(label, cond, Box::new(Self::from(*block)))
+            }
+            ParseBlk::Loop(label, block) => Self::Loop(label, Box::new(Self::from(*block))),
+            ParseBlk::Proc(name, sig, block) => {
+                Self::Proc(name, Proc::new(sig, Self::from(*block)))
+            }
+            ParseBlk::Break(label) => Self::Break(label),
+            ParseBlk::Continue(label) => Self::Continue(label),
+            ParseBlk::Return(val) => Self::Return(val),
+            ParseBlk::Try(body, catch, finally) => Self::Try(
+                Box::new(Self::from(*body)),
+                catch.map(|(var, block)| (var, Box::new(Self::from(*block)))),
+                finally.map(|block| Box::new(Self::from(*block))),
+            ),
         }
     }
 }
 
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
 impl Block {
-    pub fn eval_with_args(
-        &self,
-        name: &str,
-        args: Vec<String>,
-        jobs: &SharedJobs,
-        ns: &mut NameSpace,
-    ) -> anyhow::Result<()> {
-        ns.mark();
-        ns.set_args(name, args);
-        self.eval(jobs, ns)?;
-        ns.drop();
-        Ok(())
+    fn write(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        match self {
+            Self::Single(cmd) => write!(f, "{}", cmd),
+            Self::Not(block) => {
+                write!(f, "! ")?;
+                block.write(f, depth)
+            }
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
+                lhs.write(f, depth)?;
+                let op = if let Self::And(..) = self { "&&" } else { "||" };
+                write!(f, " {} ", op)?;
+                rhs.write(f, depth)
+            }
+            Self::Multi(blocks) if blocks.is_empty() => write!(f, "{{}}"),
+            Self::Multi(blocks) => {
+                writeln!(f, "{{")?;
+                for (_, block) in blocks {
+                    write!(f, "{}", indent(depth + 1))?;
+                    block.write(f, depth + 1)?;
+                    writeln!(f)?;
+                }
+                write!(f, "{}}}", indent(depth))
+            }
+            Self::If(branches, otherwise) => {
+                for (i, (cond, block)) in branches.iter().enumerate() {
+                    let keyword = if i == 0 { "if" } else { " elif" };
+                    write!(f, "{} {} ", keyword, cond)?;
+                    block.write_body(f, depth)?;
+                }
+                if let Some(block) = otherwise {
+                    write!(f, " else ")?;
+                    block.write_body(f, depth)?;
+                }
+                Ok(())
+            }
+            Self::Case(cond, arms) => {
+                writeln!(f, "case {} {{", cond)?;
+                for (pats, guard, block) in arms {
+                    let pats = pats.iter().map(|pat| pat.to_string()).collect::<Vec<_>>();
+                    write!(f, "{}{}", indent(depth + 1), pats.join(" | "))?;
+                    if let Some(guard) = guard {
+                        write!(f, " if {}", guard)?;
+                    }
+                    write!(f, " => ")?;
+                    block.write(f, depth + 1)?;
+                    writeln!(f)?;
+                }
+                write!(f, "{}}}", indent(depth))
+            }
+            Self::For(label, head, block) => {
+                write_label(f, label)?;
+                write!(f, "for {} ", head)?;
+                block.write_body(f, depth)
+            }
+            Self::While(label, cond, block) => {
+                write_label(f, label)?;
+                write!(f, "while {} ", cond)?;
+                block.write_body(f, depth)
+            }
+            Self::Loop(label, block) => {
+                write_label(f, label)?;
+                write!(f, "loop ")?;
+                block.write_body(f, depth)
+            }
+            Self::Proc(name, proc) => {
+                write!(f, "{}", name)?;
+                proc.write(f, depth)
+            }
+            Self::Break(label) | Self::Continue(label) => {
+                let keyword = if let Self::Break(_) = self {
+                    "break"
+                } else {
+                    "continue"
+                };
+                write!(f, "{}", keyword)?;
+                if let Some(label) = label {
+                    write!(f, " '{}", label)?;
+                }
+                Ok(())
+            }
+            Self::Return(Some(val)) => write!(f, "return {}", val),
+            Self::Return(None) => write!(f, "return"),
+            Self::Try(body, catch, finally) => {
+                write!(f, "try ")?;
+                body.write_body(f, depth)?;
+                if let Some((var, block)) = catch {
+                    write!(f, " catch ")?;
+                    if let Some(var) = var {
+                        write!(f, "{} ", var)?;
+                    }
+                    block.write_body(f, depth)?;
+                }
+                if let Some(block) = finally {
+                    write!(f, " finally ")?;
+                    block.write_body(f, depth)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn write_body(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        match self {
+            Self::Multi(_) => self.write(f, depth),
+            block => Self::Multi(vec![(1, block.clone())]).write(f, depth),
+        }
     }
 
     pub fn eval(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<()> {
-        self.eval_inner(jobs, ns)?;
-        Ok(())
+        match self.eval_inner(jobs, ns)? {
+            State::Returned(_) => anyhow::bail!("\"return\" can only be used inside a proc."),
+            State::Breaked(Some(label)) | State::Continued(Some(label)) => {
+                anyhow::bail!("The loop label \"'{}\" is not defined.", label)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn eval_proc(
+        &self,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+    ) -> anyhow::Result<Option<Value>> {
+        match self.eval_inner(jobs, ns)? {
+            State::Returned(val) => Ok(val),
+            State::Breaked(Some(label)) | State::Continued(Some(label)) => {
+                anyhow::bail!("The loop label \"'{}\" is not defined.", label)
+            }
+            _ => Ok(None),
+        }
     }
 
-    fn eval_inner(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<State> {
+    pub(super) fn eval_inner(
+        &self,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+    ) -> anyhow::Result<State> {
         match self {
             Self::Single(cmd) => {
-                cmd.eval(jobs, ns)?;
-                let stat = jobs.wait_fg()?;
-                if let Some(Status::Exited(code)) = stat {
-                    ns.push_var("status", code.to_string());
+                let known = match cmd.eval(jobs, ns) {
+                    Ok(known) => known,
+                    Err(err) => {
+                        let mut shell = ShellError::from_anyhow(&err, None);
+                        set_status(Status::Exited(shell.status), ns);
+                        // Under errexit, a failing builtin or proc aborts
+                        // the script like a failing external command.
+                        if !ns.options().errexit || shell.kind == "interrupt" {
+                            return Err(err);
+                        }
+                        shell.kind = String::from("exit");
+                        return Err(shell.into());
+                    }
+                };
+                let stat = match (jobs.wait_fg()?, known) {
+                    (None, known) | (Some(Status::Exited(0)), known @ Some(_)) => known,
+                    (stat, _) => stat,
+                };
+                let stat = stat.unwrap_or(Status::Exited(0));
+                set_status(stat, ns);
+                if stat.interrupted() {
+                    return Err(ShellError::new("interrupt", "Interrupted", stat.code()).into());
                 }
+                errexit(stat, ns)?;
                 Ok(State::Normal)
             }
+            Self::Not(block) => {
+                let state = unchecked(ns, |ns| block.eval_inner(jobs, ns))?;
+                let code = if status(ns) == 0 { 1 } else { 0 };
+                set_status(Status::Exited(code), ns);
+                Ok(state)
+            }
+            Self::And(lhs, rhs) => match unchecked(ns, |ns| lhs.eval_inner(jobs, ns))? {
+                State::Normal if status(ns) == 0 => rhs.eval_inner(jobs, ns),
+                state => Ok(state),
+            },
+            Self::Or(lhs, rhs) => match unchecked(ns, |ns| lhs.eval_inner(jobs, ns))? {
+                State::Normal if status(ns) != 0 => rhs.eval_inner(jobs, ns),
+                state => Ok(state),
+            },
             Self::Multi(lines) => {
-                ns.mark();
-                for line in lines.iter() {
-                    let state = line.eval_inner(jobs, ns)?;
-                    match state {
-                        State::Normal => continue,
-                        State::Breaked | State::Continued => {
-                            ns.drop();
+                let saved = ns.line();
+                let res = scoped(ns, |ns| {
+                    for (line, block) in lines.iter() {
+                        ns.set_line(*line);
+                        let state = block.eval_inner(jobs, ns)?;
+                        if state != State::Normal {
                             return Ok(state);
                         }
                     }
+                    Ok(State::Normal)
+                });
+                ns.set_line(saved);
+                res
+            }
+            Self::If(branches, otherwise) => {
+                for (cond, block) in branches.iter() {
+                    if truthy(&cond.eval(jobs, ns)?) {
+                        return block.eval_inner(jobs, ns);
+                    }
+                }
+
+                match otherwise {
+                    Some(block) => block.eval_inner(jobs, ns),
+                    None => Ok(State::Normal),
                 }
-                ns.drop();
-                Ok(State::Normal)
             }
-            Self::If(cond, first, second) => {
-                let cond = matches!(
-                    cond.eval(jobs)?.to_lowercase().as_str(),
-                    "1" | "y" | "yes" | "true"
-                );
+            Self::Case(cond, arms) => {
+                let cond = cond.eval_value(jobs, ns)?;
+                for (pats, guard, block) in arms.iter() {
+                    for pat in pats.iter() {
+                        let binds = match pat.matches(&cond, jobs, ns)? {
+                            Some(binds) => binds,
+                            None => continue,
+                        };
 
-                let state = if cond {
-                    first.eval_inner(jobs, ns)?
-                } else if let Some(sec) = second {
-                    sec.eval_inner(jobs, ns)?
-                } else {
-                    State::Normal
+                        ns.mark();
+                        for (key, val) in binds {
+                            ns.push_var(key, val);
+                        }
+                        let res = match guard.as_ref().map(|guard| guard.eval(jobs, ns)) {
+                            Some(Ok(guard)) if !truthy(&guard) => None,
+                            Some(Err(e)) => Some(Err(e)),
+                            _ => Some(block.eval_inner(jobs, ns)),
+                        };
+                        ns.drop();
+
+                        if let Some(res) = res {
+                            return res;
+                        }
+                    }
+                }
+                Ok(State::Normal)
+            }
+            Self::For(
+                label,
+                head @ ForHead {
+                    parallel: Some(limit),
+                    ..
+                },
+                block,
+            ) => {
+                let limit = match limit.eval_value(jobs, ns)?.cast(Type::Int)? {
+                    Value::Int(limit) if limit > 0 => limit as usize,
+                    _ => anyhow::bail!("The number of parallel jobs must be positive."),
                 };
+                let items = items(head, jobs, ns)?;
+                let (statuses, state) =
+                    parallel::run(label, &head.vars, items, limit, block, jobs, ns)?;
 
+                let codes = statuses.iter().map(Status::code).collect::<Vec<_>>();
+                let failed = statuses
+                    .iter()
+                    .zip(codes.iter())
+                    .find(|(_, code)| **code != 0);
+                ns.push_gvar(
+                    "statuses",
+                    Value::List(codes.iter().map(|code| Value::Int(*code as i64)).collect()),
+                );
+                let stat = failed.map_or(Status::Exited(0), |(stat, _)| *stat);
+                set_status(stat, ns);
+                if stat.interrupted() {
+                    return Err(ShellError::new("interrupt", "Interrupted", stat.code()).into());
+                }
+                errexit(stat, ns)?;
                 Ok(state)
             }
-            Self::Case(cond, blocks) => {
-                let cond = cond.eval(jobs)?;
-                for (pats, block) in blocks.iter() {
-                    let pats = pats
-                        .iter()
-                        .map(|pat| pat.eval(jobs))
-                        .collect::<Result<Vec<_>, _>>()?;
-                    if pats.into_iter().any(|pat| pat == cond) {
-                        return Ok(block.eval_inner(jobs, ns)?);
+            Self::For(label, head, block) => scoped(ns, |ns| {
+                for (key, val) in items(head, jobs, ns)? {
+                    match head.vars.as_slice() {
+                        [k, v] => {
+                            ns.push_var(k, key);
+                            ns.push_var(v, val);
+                        }
+                        [var] => ns.push_var(var, val),
+                        _ => unreachable!(),
+                    }
+                    match step(block.eval_inner(jobs, ns)?, label) {
+                        Ok(true) => continue,
+                        Ok(false) => break,
+                        Err(state) => return Ok(state),
                     }
                 }
                 Ok(State::Normal)
-            }
-            Self::For(c, iter, block) => {
-                ns.mark();
-                for val in iter.eval(jobs)?.split('\n') {
-                    ns.push_var(c, val);
-                    let state = block.eval_inner(jobs, ns)?;
-                    match state {
-                        State::Normal | State::Continued => continue,
-                        State::Breaked => break,
+            }),
+            Self::While(label, cond, block) => {
+                while truthy(&cond.eval(jobs, ns)?) {
+                    match step(block.eval_inner(jobs, ns)?, label) {
+                        Ok(true) => continue,
+                        Ok(false) => break,
+                        Err(state) => return Ok(state),
                     }
                 }
-                ns.drop();
                 Ok(State::Normal)
             }
-            Self::While(cond, block) => {
-                while matches!(
-                    cond.eval(jobs)?.to_lowercase().as_str(),
-                    "1" | "y" | "yes" | "true"
-                ) {
-                    let state = block.eval_inner(jobs, ns)?;
-                    match state {
-                        State::Normal | State::Continued => continue,
-                        State::Breaked => break,
-                    }
+            Self::Loop(label, block) => loop {
+                match step(block.eval_inner(jobs, ns)?, label) {
+                    Ok(true) => continue,
+                    Ok(false) => break Ok(State::Normal),
+                    Err(state) => break Ok(state),
                 }
+            },
+            Self::Proc(name, proc) => {
+                ns.push_proc(name, proc.defined_at(ns.source_location()));
                 Ok(State::Normal)
             }
-            Self::Proc(name, block) => {
-                ns.push_proc(name, (**block).clone());
-                Ok(State::Normal)
+            Self::Break(label) => Ok(State::Breaked(label.clone())),
+            Self::Continue(label) => Ok(State::Continued(label.clone())),
+            Self::Return(val) => Ok(State::Returned(match val {
+                Some(val) => Some(val.eval_value(jobs, ns)?),
+                None => None,
+            })),
+            Self::Try(body, catch, finally) => {
+                let res = match (body.eval_inner(jobs, ns), catch) {
+                    (Err(e), Some((var, block))) if !fatal(&e) => {
+                        let err = ShellError::from_anyhow(&e, ns.location());
+                        ns.mark();
+                        if let Some(var) = var {
+                            ns.push_var(var, err.to_value());
+                        }
+                        let res = block.eval_inner(jobs, ns);
+                        ns.drop();
+                        res
+                    }
+                    (res, _) => res,
+                };
+
+                if let Some(finally) = finally {
+                    let state = finally.eval_inner(jobs, ns)?;
+                    if state != State::Normal {
+                        return Ok(state);
+                    }
+                }
+                res
             }
-            Self::Break => Ok(State::Breaked),
-            Self::Continue => Ok(State::Continued),
         }
     }
 }
+
+type Items = Box<dyn Iterator<Item = (Value, Value)>>;
+
+fn items(head: &ForHead, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Items> {
+    let val = head.iter.eval_value(jobs, ns)?;
+    let stride = match &head.step {
+        Some(step) => match step.eval_value(jobs, ns)?.cast(Type::Int)? {
+            Value::Int(0) => anyhow::bail!("The step of a range can't be 0."),
+            Value::Int(i) => Some(i),
+            _ => unreachable!(),
+        },
+        None => None,
+    };
+
+    if let Value::Str(s) = &val {
+        if let Some(nums) = range(s, stride.unwrap_or(1)) {
+            return Ok(Box::new(
+                nums.enumerate()
+                    .map(|(i, n)| (Value::Int(i as i64), Value::Int(n))),
+            ));
+        }
+    }
+    if stride.is_some() {
+        anyhow::bail!("\"step\" can only be used with a range.");
+    }
+
+    Ok(match val {
+        Value::Map(entries) if head.vars.len() == 1 => Box::new(
+            entries
+                .into_iter()
+                .map(|(k, _)| (Value::Null, Value::from(k))),
+        ),
+        Value::Map(entries) => Box::new(entries.into_iter().map(|(k, v)| (Value::from(k), v))),
+        Value::List(items) => Box::new(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| (Value::Int(i as i64), item)),
+        ),
+        s => Box::new(
+            s.to_string()
+                .split('\n')
+                .map(Value::from)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| (Value::Int(i as i64), line)),
+        ),
+    })
+}
+
+fn range(s: &str, stride: i64) -> Option<impl Iterator<Item = i64>> {
+    let (start, end) = s.split_once("..")?;
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+    let start = start.parse::<i64>().ok()?;
+    let end = end.parse::<i64>().ok()?;
+
+    let within = move |i: &i64| match (stride > 0, inclusive) {
+        (true, true) => *i <= end,
+        (true, false) => *i < end,
+        (false, true) => *i >= end,
+        (false, false) => *i > end,
+    };
+    Some(std::iter::successors(Some(start), move |i| i.checked_add(stride)).take_while(within))
+}
+
+pub(super) fn step(state: State, label: &Option<String>) -> Result<bool, State> {
+    match state {
+        State::Normal => Ok(true),
+        State::Continued(None) => Ok(true),
+        State::Breaked(None) => Ok(false),
+        State::Continued(ref target) if target == label => Ok(true),
+        State::Breaked(ref target) if target == label => Ok(false),
+        state => Err(state),
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+fn write_label(f: &mut fmt::Formatter, label: &Option<String>) -> fmt::Result {
+    match label {
+        Some(label) => write!(f, "'{}: ", label),
+        None => Ok(()),
+    }
+}
+
+fn truthy(cond: &str) -> bool {
+    matches!(cond.to_lowercase().as_str(), "1" | "y" | "yes" | "true")
+}
+
+pub(super) fn status(ns: &NameSpace) -> i32 {
+    ns.get_var("status")
+        .and_then(|code| code.to_string().parse().ok())
+        .unwrap_or(0)
+}
+
+fn set_status(stat: Status, ns: &mut NameSpace) {
+    ns.push_gvar("status", stat.code() as i64);
+    let signal = match stat {
+        Status::Signaled(sig) => sig.to_string(),
+        Status::Exited(_) => String::new(),
+    };
+    ns.push_gvar("signal", signal);
+}
+
+fn scoped<F>(ns: &mut NameSpace, f: F) -> anyhow::Result<State>
+where
+    F: FnOnce(&mut NameSpace) -> anyhow::Result<State>,
+{
+    ns.mark();
+    let res = f(ns);
+    ns.drop();
+    res
+}
+
+fn unchecked<F>(ns: &mut NameSpace, f: F) -> anyhow::Result<State>
+where
+    F: FnOnce(&mut NameSpace) -> anyhow::Result<State>,
+{
+    let errexit = ns.options().errexit;
+    ns.options_mut().errexit = false;
+    let res = f(ns);
+    ns.options_mut().errexit = errexit;
+    res
+}
+
+fn fatal(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<ShellError>() {
+        Some(err) => err.kind == "exit" || err.kind == "interrupt",
+        None => false,
+    }
+}
+
+fn errexit(stat: Status, ns: &NameSpace) -> anyhow::Result<()> {
+    if !ns.options().errexit {
+        return Ok(());
+    }
+    match stat {
+        Status::Exited(code) if code != 0 => Err(ShellError::new(
+            "exit",
+            format!("Command exited with status {}.", code),
+            code,
+        )
+        .into()),
+        Status::Signaled(sig) if !stat.stopped() && !stat.continued() => Err(ShellError::new(
+            "exit",
+            format!("Command was killed by {}.", sig),
+            stat.code(),
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,212 @@
+use super::Io;
+use crate::eval::{NameSpace, Value};
+use crate::job::SharedJobs;
+use anyhow::Context;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub fn cd<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let (physical, args) = flags(args.as_ref());
+    let dir = match args.as_slice() {
+        [] => PathBuf::from(
+            ns.get_var("HOME")
+                .context("Failed to get the home directory.")?
+                .to_string(),
+        ),
+        [dir] if dir == "-" => {
+            let dir = ns
+                .get_var("OLDPWD")
+                .context("The previous directory is not set.")?
+                .to_string();
+            writeln!(io.stdout(), "{}", dir)?;
+            PathBuf::from(dir)
+        }
+        [dir] => match search(dir, ns) {
+            Some(dir) => {
+                writeln!(io.stdout(), "{}", dir.display())?;
+                dir
+            }
+            None => PathBuf::from(dir),
+        },
+        _ => anyhow::bail!("Unexpected args number."),
+    };
+
+    chdir(&dir, physical, jobs, ns)
+}
+
+pub fn pushd<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let (physical, args) = flags(args.as_ref());
+    let cwd = pwd(ns);
+    let dir = match args.as_slice() {
+        [] => ns
+            .dirs()
+            .last()
+            .cloned()
+            .context("The directory stack is empty.")?,
+        [dir] => search(dir, ns).unwrap_or_else(|| PathBuf::from(dir)),
+        _ => anyhow::bail!("Unexpected args number."),
+    };
+
+    chdir(&dir, physical, jobs, ns)?;
+    if args.is_empty() {
+        ns.dirs_mut().pop();
+    }
+    ns.dirs_mut().push(cwd);
+    print_dirs(io, ns, false)
+}
+
+pub fn popd<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let (physical, args) = flags(args.as_ref());
+    if !args.is_empty() {
+        anyhow::bail!("Unexpected args number.");
+    }
+
+    let dir = ns
+        .dirs()
+        .last()
+        .cloned()
+        .context("The directory stack is empty.")?;
+    chdir(&dir, physical, jobs, ns)?;
+    ns.dirs_mut().pop();
+    print_dirs(io, ns, false)
+}
+
+pub fn dirs<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let mut verbose = false;
+    for arg in args.as_ref() {
+        match arg.as_ref() {
+            "-c" => ns.dirs_mut().clear(),
+            "-v" => verbose = true,
+            arg => anyhow::bail!("Unknown option \"{}\".", arg),
+        }
+    }
+
+    print_dirs(io, ns, verbose)
+}
+
+fn flags<T: AsRef<str>>(args: &[T]) -> (bool, Vec<String>) {
+    let mut physical = false;
+    let mut rest = Vec::new();
+    for arg in args.iter().map(AsRef::as_ref) {
+        match arg {
+            "-L" if rest.is_empty() => physical = false,
+            "-P" if rest.is_empty() => physical = true,
+            _ => rest.push(String::from(arg)),
+        }
+    }
+    (physical, rest)
+}
+
+fn search(dir: &str, ns: &NameSpace) -> Option<PathBuf> {
+    let path = Path::new(dir);
+    if path.is_absolute()
+        || matches!(
+            path.components().next(),
+            Some(Component::CurDir | Component::ParentDir)
+        )
+    {
+        return None;
+    }
+
+    let cdpath = ns.get_var("CDPATH")?.to_string();
+    cdpath
+        .split(':')
+        .filter(|base| !base.is_empty())
+        .map(|base| Path::new(base).join(path))
+        .find(|candidate| candidate.is_dir())
+}
+
+fn chdir(dir: &Path, physical: bool, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<()> {
+    let old = pwd(ns);
+    let dir = if physical {
+        fs::canonicalize(dir)
+            .with_context(|| format!("Failed to resolve \"{}\".", dir.display()))?
+    } else {
+        normalize(&old.join(dir))
+    };
+
+    env::set_current_dir(&dir).context("Failed to set current dir.")?;
+    ns.export_var("OLDPWD", Value::from(old.to_string_lossy().as_ref()))?;
+    ns.export_var("PWD", Value::from(dir.to_string_lossy().as_ref()))?;
+
+    if let Some(proc) = ns.get_proc("chpwd") {
+        proc.call("chpwd", Vec::new(), jobs, ns)?;
+    }
+    Ok(())
+}
+
+// `$PWD` may not refer to the current directory when inherited from the
+// parent process.
+fn pwd(ns: &NameSpace) -> PathBuf {
+    let cwd = env::current_dir().unwrap_or_default();
+    match ns.get_var("PWD").map(|pwd| PathBuf::from(pwd.to_string())) {
+        Some(pwd) if pwd.is_absolute() && same_file(&pwd, &cwd) => pwd,
+        _ => cwd,
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                res.pop();
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn print_dirs(io: &mut Io, ns: &NameSpace, verbose: bool) -> anyhow::Result<()> {
+    let home = ns
+        .get_var("HOME")
+        .map(|home| PathBuf::from(home.to_string()))
+        .filter(|home| home.is_absolute());
+    let dirs = std::iter::once(pwd(ns))
+        .chain(ns.dirs().iter().rev().cloned())
+        .map(
+            |dir| match home.as_ref().and_then(|home| dir.strip_prefix(home).ok()) {
+                Some(rest) if rest.as_os_str().is_empty() => String::from("~"),
+                Some(rest) => format!("~/{}", rest.display()),
+                None => dir.to_string_lossy().into_owned(),
+            },
+        );
+
+    if verbose {
+        for (i, dir) in dirs.enumerate() {
+            writeln!(io.stdout(), "{:2}  {}", i, dir)?;
+        }
+    } else {
+        writeln!(io.stdout(), "{}", dirs.collect::<Vec<_>>().join(" "))?;
+    }
+    Ok(())
+}
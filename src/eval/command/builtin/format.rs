@@ -0,0 +1,199 @@
+extern crate csv;
+extern crate serde_json;
+extern crate toml;
+
+use crate::eval::Value;
+use anyhow::Context;
+use serde_json::Value as Json;
+use toml::Value as Toml;
+
+pub fn from(args: &[Value], input: Value) -> anyhow::Result<Value> {
+    let (format, text) = match args {
+        [format] => (format.to_string(), text(input)),
+        [format, path] => (
+            format.to_string(),
+            std::fs::read_to_string(path.to_string())
+                .with_context(|| format!("Failed to read \"{}\".", path))?,
+        ),
+        _ => anyhow::bail!("Usage: from <format> [file]"),
+    };
+
+    match format.as_str() {
+        "json" => Ok(from_json(
+            serde_json::from_str(&text).context("Failed to parse JSON.")?,
+        )),
+        "csv" => from_csv(&text),
+        "toml" => Ok(from_toml(
+            toml::from_str(&text).context("Failed to parse TOML.")?,
+        )),
+        _ => anyhow::bail!("Unknown format: \"{}\"", format),
+    }
+}
+
+pub fn to(args: &[Value], input: Value) -> anyhow::Result<Value> {
+    let format = match args {
+        [format] => format.to_string(),
+        _ => anyhow::bail!("Usage: to <format>"),
+    };
+
+    let text = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&to_json(input))?,
+        "csv" => to_csv(input)?,
+        "toml" => match input {
+            map @ Value::Map(_) => toml::to_string_pretty(&to_toml(map)?)?,
+            val => anyhow::bail!("TOML needs a map, found {}.", val.type_name()),
+        },
+        _ => anyhow::bail!("Unknown format: \"{}\"", format),
+    };
+
+    Ok(Value::Str(text))
+}
+
+fn text(input: Value) -> String {
+    match input {
+        Value::List(lines) => lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        s => s.to_string(),
+    }
+}
+
+fn from_json(json: Json) -> Value {
+    match json {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Json::String(s) => Value::Str(s),
+        Json::Array(items) => Value::List(items.into_iter().map(from_json).collect()),
+        Json::Object(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, val)| (key, from_json(val)))
+                .collect(),
+        ),
+    }
+}
+
+fn to_json(val: Value) -> Json {
+    match val {
+        Value::Null => Json::Null,
+        Value::Bool(b) => Json::Bool(b),
+        Value::Str(s) => Json::String(s),
+        Value::Int(i) => Json::from(i),
+        Value::Float(x) => Json::from(x),
+        Value::List(items) => Json::Array(items.into_iter().map(to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, val)| (key, to_json(val)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_csv(text: &str) -> anyhow::Result<Value> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .context("Failed to parse CSV.")?
+        .iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.context("Failed to parse CSV.")?;
+        rows.push(Value::Map(
+            headers
+                .iter()
+                .cloned()
+                .zip(record.iter().map(Value::from))
+                .collect(),
+        ));
+    }
+
+    Ok(Value::List(rows))
+}
+
+fn to_csv(val: Value) -> anyhow::Result<String> {
+    let rows = match val {
+        Value::List(items) => items,
+        map @ Value::Map(_) => vec![map],
+        s => vec![Value::List(vec![s])],
+    };
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows.iter() {
+        for key in row.keys() {
+            let key = key.to_string();
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if !columns.is_empty() {
+        writer.write_record(&columns)?;
+    }
+    for row in rows {
+        let record = match row {
+            map @ Value::Map(_) => columns
+                .iter()
+                .map(|col| map.field(col).map(|v| v.to_string()).unwrap_or_default())
+                .collect::<Vec<_>>(),
+            Value::List(items) => items.iter().map(|item| item.to_string()).collect(),
+            s => vec![s.to_string()],
+        };
+        writer.write_record(&record)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?
+        .trim_end()
+        .to_string())
+}
+
+fn from_toml(toml: Toml) -> Value {
+    match toml {
+        Toml::Boolean(b) => Value::Bool(b),
+        Toml::Integer(i) => Value::Int(i),
+        Toml::Float(x) => Value::Float(x),
+        Toml::String(s) => Value::Str(s),
+        Toml::Datetime(d) => Value::Str(d.to_string()),
+        Toml::Array(items) => Value::List(items.into_iter().map(from_toml).collect()),
+        Toml::Table(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, val)| (key, from_toml(val)))
+                .collect(),
+        ),
+    }
+}
+
+fn to_toml(val: Value) -> anyhow::Result<Toml> {
+    Ok(match val {
+        Value::Null => anyhow::bail!("TOML can't represent a null value."),
+        Value::Bool(b) => Toml::Boolean(b),
+        Value::Str(s) => Toml::String(s),
+        Value::Int(i) => Toml::Integer(i),
+        Value::Float(x) => Toml::Float(x),
+        Value::List(items) => Toml::Array(
+            items
+                .into_iter()
+                .map(to_toml)
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        Value::Map(entries) => Toml::Table(
+            entries
+                .into_iter()
+                .filter(|(_, val)| *val != Value::Null)
+                .map(|(key, val)| Ok((key, to_toml(val)?)))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+    })
+}
@@ -0,0 +1,167 @@
+extern crate nix;
+
+use super::super::{Redirects, Stdin};
+use crate::eval::NameSpace;
+use crate::job::SharedJobs;
+use nix::errno::Errno;
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::unistd;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::thread;
+use std::time::Instant;
+
+// Stdin is read a byte at a time, so that nothing past what the builtin
+// consumes is lost to the commands run after it.
+pub struct Io {
+    stdin: Input,
+    stdout: Output,
+}
+
+enum Input {
+    Inherit,
+    File(File),
+    HereDoc(io::Cursor<Vec<u8>>),
+}
+
+enum Output {
+    Inherit,
+    File(File),
+    Capture(Vec<u8>),
+}
+
+impl Io {
+    pub fn new(reds: &Redirects, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Self> {
+        Self::with_output(reds, jobs, ns, Output::Inherit)
+    }
+
+    pub fn capture(reds: &Redirects, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Self> {
+        Self::with_output(reds, jobs, ns, Output::Capture(Vec::new()))
+    }
+
+    fn with_output(
+        reds: &Redirects,
+        jobs: &SharedJobs,
+        ns: &NameSpace,
+        stdout: Output,
+    ) -> anyhow::Result<Self> {
+        let stdin = match reds.stdin(jobs, ns)? {
+            Some(Stdin::File(file)) => Input::File(file),
+            Some(Stdin::HereDoc(text)) => Input::HereDoc(io::Cursor::new(text)),
+            None => Input::Inherit,
+        };
+        let stdout = match reds.stdout(jobs, ns)? {
+            Some(file) => Output::File(file),
+            None => stdout,
+        };
+        Ok(Self { stdin, stdout })
+    }
+
+    pub fn pipe(&mut self, stdin: Stdin) {
+        if let Input::Inherit = self.stdin {
+            self.stdin = match stdin {
+                Stdin::File(file) => Input::File(file),
+                Stdin::HereDoc(text) => Input::HereDoc(io::Cursor::new(text)),
+            };
+        }
+    }
+
+    pub fn capture_stdout<T>(f: impl FnOnce() -> T) -> anyhow::Result<(T, String)> {
+        io::stdout().flush()?;
+        let (read, write) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        let reader = thread::spawn(move || {
+            let mut buf = Vec::new();
+            unsafe { File::from_raw_fd(read) }
+                .read_to_end(&mut buf)
+                .map(|_| buf)
+        });
+
+        let saved = fcntl(1, FcntlArg::F_DUPFD_CLOEXEC(3))?;
+        unistd::dup2(write, 1)?;
+        unistd::close(write)?;
+        let res = f();
+        io::stdout().flush()?;
+        unistd::dup2(saved, 1)?;
+        unistd::close(saved)?;
+
+        let buf = reader
+            .join()
+            .map_err(|_| anyhow::anyhow!("Failed to capture the output."))??;
+        Ok((res, String::from_utf8_lossy(&buf).into_owned()))
+    }
+
+    pub fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    pub fn captured(self) -> String {
+        match self.stdout {
+            Output::Capture(buf) => String::from_utf8_lossy(&buf).into_owned(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn terminal(&self) -> bool {
+        matches!(self.stdin, Input::Inherit) && unistd::isatty(0).unwrap_or(false)
+    }
+
+    pub fn read_byte(&mut self, deadline: Option<Instant>) -> io::Result<Option<u8>> {
+        let fd = match &mut self.stdin {
+            Input::HereDoc(text) => {
+                let mut buf = [0];
+                return Ok(match text.read(&mut buf)? {
+                    0 => None,
+                    _ => Some(buf[0]),
+                });
+            }
+            Input::File(file) => file.as_raw_fd(),
+            Input::Inherit => 0,
+        };
+
+        if let Some(deadline) = deadline {
+            wait(fd, deadline)?;
+        }
+        let mut buf = [0];
+        loop {
+            match unistd::read(fd, &mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(nix::Error::Sys(Errno::EINTR)) => continue,
+                Err(e) => return Err(io::Error::other(e)),
+            }
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Inherit => io::stdout().write(buf),
+            Self::File(file) => file.write(buf),
+            Self::Capture(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Inherit => io::stdout().flush(),
+            Self::File(file) => file.flush(),
+            Self::Capture(out) => out.flush(),
+        }
+    }
+}
+
+fn wait(fd: RawFd, deadline: Instant) -> io::Result<()> {
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+        match poll(&mut fds, left.as_millis().min(i32::MAX as u128) as i32) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::TimedOut)),
+            Ok(_) => return Ok(()),
+            Err(nix::Error::Sys(Errno::EINTR)) => continue,
+            Err(e) => return Err(io::Error::other(e)),
+        }
+    }
+}
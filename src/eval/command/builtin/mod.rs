@@ -0,0 +1,531 @@
+mod dir;
+mod format;
+mod io;
+mod print;
+mod read;
+mod table;
+
+pub use io::Io;
+
+use super::NameSpace;
+use crate::eval::namespace::{executable, search};
+use crate::eval::{ShellError, Value};
+use crate::job::{SharedJobs, Status};
+use anyhow::Context;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Builtin {
+    kind: BuiltinKind,
+    args: Vec<Value>,
+}
+
+impl Builtin {
+    pub fn new<T, TS>(kind: BuiltinKind, args: TS) -> Self
+    where
+        T: Into<Value>,
+        TS: IntoIterator<Item = T>,
+    {
+        Self {
+            kind,
+            args: args.into_iter().map(|s| s.into()).collect(),
+        }
+    }
+
+    pub fn eval(
+        &self,
+        io: &mut Io,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+    ) -> anyhow::Result<Status> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+
+        match self.kind {
+            BuiltinKind::Empty => (),
+            BuiltinKind::Exit => exit(&args)?,
+            BuiltinKind::Cd => dir::cd(&args, io, jobs, ns)?,
+            BuiltinKind::Pushd => dir::pushd(&args, io, jobs, ns)?,
+            BuiltinKind::Popd => dir::popd(&args, io, jobs, ns)?,
+            BuiltinKind::Dirs => dir::dirs(&args, io, ns)?,
+            BuiltinKind::Fg => fg(&args, jobs)?,
+            BuiltinKind::Jobs => writeln!(io.stdout(), "{:#?}", jobs.get()?)?,
+            BuiltinKind::Let => let_(&self.args, ns)?,
+            BuiltinKind::Export => export(&self.args, ns)?,
+            BuiltinKind::Const => const_(&self.args, ns)?,
+            BuiltinKind::Readonly => readonly(&args, io, ns)?,
+            BuiltinKind::Alias => alias(&self.args, io, ns)?,
+            BuiltinKind::Abbr => abbr(&self.args, io, ns)?,
+            BuiltinKind::Unalias => unalias(&args, ns)?,
+            BuiltinKind::Type => type_(&args, io, ns)?,
+            BuiltinKind::Hash => hash(&args, io, ns)?,
+            BuiltinKind::Source => source(&args, ns)?,
+            BuiltinKind::Throw => throw(&self.args, ns)?,
+            BuiltinKind::Set => set(&args, io, ns)?,
+            BuiltinKind::Read => return read::read(&args, io, ns),
+            BuiltinKind::Echo => print::echo(&self.args, io)?,
+            BuiltinKind::Printf => print::printf(&self.args, io)?,
+            _ => writeln!(io.stdout(), "{}", self.eval_value(None)?.render())?,
+        }
+
+        Ok(Status::Exited(0))
+    }
+
+    pub fn eval_value(&self, input: Option<Value>) -> anyhow::Result<Value> {
+        let input = input.unwrap_or_else(|| Value::List(Vec::new()));
+        match self.kind {
+            BuiltinKind::Ls => table::ls(&self.args),
+            BuiltinKind::Where => table::where_(&self.args, input),
+            BuiltinKind::Select => table::select(&self.args, input),
+            BuiltinKind::SortBy => table::sort_by(&self.args, input),
+            BuiltinKind::From => format::from(&self.args, input),
+            BuiltinKind::To => format::to(&self.args, input),
+            _ => anyhow::bail!("This builtin can't be used in a pipeline."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinKind {
+    Empty,
+    Exit,
+    Cd,
+    Pushd,
+    Popd,
+    Dirs,
+    Fg,
+    Jobs,
+    Let,
+    Export,
+    Const,
+    Readonly,
+    Alias,
+    Abbr,
+    Unalias,
+    Type,
+    Hash,
+    Source,
+    Throw,
+    Set,
+    Read,
+    Echo,
+    Printf,
+    Env,
+    Ls,
+    Where,
+    Select,
+    SortBy,
+    From,
+    To,
+}
+
+impl BuiltinKind {
+    pub const NAMES: &'static [&'static str] = &[
+        "exit", "cd", "pushd", "popd", "dirs", "fg", "jobs", "let", "export", "const", "readonly",
+        "alias", "abbr", "unalias", "type", "hash", "source", "throw", "error", "set", "read",
+        "echo", "printf", "env", "ls", "where", "select", "sort-by", "from", "to",
+    ];
+
+    pub fn new<T: AsRef<str>>(name: T) -> Option<Self> {
+        Some(match name.as_ref() {
+            "" => Self::Empty,
+            "exit" => Self::Exit,
+            "cd" => Self::Cd,
+            "pushd" => Self::Pushd,
+            "popd" => Self::Popd,
+            "dirs" => Self::Dirs,
+            "fg" => Self::Fg,
+            "jobs" => Self::Jobs,
+            "let" => Self::Let,
+            "export" => Self::Export,
+            "const" => Self::Const,
+            "readonly" => Self::Readonly,
+            "alias" => Self::Alias,
+            "abbr" => Self::Abbr,
+            "unalias" => Self::Unalias,
+            "type" => Self::Type,
+            "hash" => Self::Hash,
+            "source" => Self::Source,
+            "throw" | "error" => Self::Throw,
+            "set" => Self::Set,
+            "read" => Self::Read,
+            "echo" => Self::Echo,
+            "printf" => Self::Printf,
+            "env" => Self::Env,
+            "ls" => Self::Ls,
+            "where" => Self::Where,
+            "select" => Self::Select,
+            "sort-by" => Self::SortBy,
+            "from" => Self::From,
+            "to" => Self::To,
+            _ => return None,
+        })
+    }
+
+    pub fn structured(&self) -> bool {
+        matches!(
+            self,
+            Self::Env
+                | Self::Ls
+                | Self::Where
+                | Self::Select
+                | Self::SortBy
+                | Self::From
+                | Self::To
+        )
+    }
+
+    // `ls` leaves the options it doesn't know to the external command.
+    pub fn accepts(&self, args: &[Value]) -> bool {
+        match self {
+            Self::Ls => args.iter().all(|arg| {
+                let arg = arg.to_string();
+                arg == "-a" || !arg.starts_with('-')
+            }),
+            _ => true,
+        }
+    }
+}
+
+pub fn exit<T: AsRef<str>, TS: AsRef<[T]>>(args: TS) -> anyhow::Result<()> {
+    let args = args.as_ref();
+    let code = match args.len() {
+        0 => 0,
+        1 => args[0]
+            .as_ref()
+            .parse::<i32>()
+            .context("Failed to parse a number.")?,
+        _ => anyhow::bail!("Unnexpected args number."),
+    };
+    std::process::exit(code);
+}
+
+pub fn fg<T: AsRef<str>, TS: AsRef<[T]>>(args: TS, jobs: &SharedJobs) -> anyhow::Result<()> {
+    let args = args.as_ref();
+    if args.len() != 1 {
+        anyhow::bail!("Unexpected args number.");
+    }
+
+    let id = args[0].as_ref();
+
+    jobs.with(|jobs| {
+        let id = jobs
+            .id_from_pid(id.parse().context("Failed to parse a number.")?)
+            .context("Can't find such a process.")?;
+
+        jobs.move_to_fg(id)?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+pub fn let_(args: &[Value], ns: &mut NameSpace) -> anyhow::Result<()> {
+    if args.len() != 3 {
+        anyhow::bail!("Unnexpected args number.");
+    }
+
+    let name = args[0].to_string();
+    let value = args[2].clone();
+    match args[1].to_string().as_str() {
+        "=" => {
+            ns.writable_var(&name)?;
+            ns.push_var(name, value);
+        }
+        "+=" => {
+            let mut var = ns.get_var(&name).unwrap_or_else(|| Value::List(Vec::new()));
+            var.append(value)?;
+            ns.set_var(name, var)?;
+        }
+        _ => anyhow::bail!("Missing \"=\"."),
+    }
+    Ok(())
+}
+
+pub fn export(args: &[Value], ns: &mut NameSpace) -> anyhow::Result<()> {
+    let (name, value) = match args {
+        [name] => {
+            let name = name.to_string();
+            let value = ns
+                .get_var(&name)
+                .with_context(|| format!("Variable \"{}\" is not defined.", name))?;
+            (name, value)
+        }
+        [name, eq, value] if eq.to_string() == "=" => (name.to_string(), value.clone()),
+        [_, _, _] => anyhow::bail!("Missing \"=\"."),
+        _ => anyhow::bail!("Unnexpected args number."),
+    };
+
+    ns.export_var(name, value)
+}
+
+pub fn const_(args: &[Value], ns: &mut NameSpace) -> anyhow::Result<()> {
+    match args {
+        [name, eq, value] if eq.to_string() == "=" => {
+            let name = name.to_string();
+            ns.writable_var(&name)?;
+            ns.const_var(name, value.clone())
+        }
+        [_, _, _] => anyhow::bail!("Missing \"=\"."),
+        _ => anyhow::bail!("Unnexpected args number."),
+    }
+}
+
+pub fn readonly<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let args = args.as_ref();
+    if args.is_empty() {
+        for name in ns.readonly_names() {
+            writeln!(io.stdout(), "{}", name)?;
+        }
+    }
+    for name in args {
+        ns.readonly_var(name)?;
+    }
+    Ok(())
+}
+
+pub fn alias(args: &[Value], io: &mut Io, ns: &mut NameSpace) -> anyhow::Result<()> {
+    match args {
+        [] => {
+            let mut aliases = ns.aliases().iter().collect::<Vec<_>>();
+            aliases.sort();
+            for (name, words) in aliases {
+                writeln!(io.stdout(), "alias {} = {}", name, words.join(" "))?;
+            }
+        }
+        [name, eq, words @ ..] if eq.to_string() == "=" => {
+            if words.is_empty() {
+                anyhow::bail!("Missing the alias body.");
+            }
+            ns.push_alias(
+                name.to_string(),
+                words.iter().map(|word| word.to_string()).collect(),
+            );
+        }
+        _ => anyhow::bail!("Missing \"=\"."),
+    }
+    Ok(())
+}
+
+pub fn abbr(args: &[Value], io: &mut Io, ns: &mut NameSpace) -> anyhow::Result<()> {
+    match args {
+        [] => {
+            let mut abbrs = ns.abbrs().iter().collect::<Vec<_>>();
+            abbrs.sort();
+            for (name, expansion) in abbrs {
+                writeln!(io.stdout(), "abbr {} = {}", name, expansion)?;
+            }
+        }
+        [name, eq, words @ ..] if eq.to_string() == "=" => {
+            if words.is_empty() {
+                anyhow::bail!("Missing the abbreviation body.");
+            }
+            let words = words
+                .iter()
+                .map(|word| word.to_string())
+                .collect::<Vec<_>>();
+            ns.push_abbr(name.to_string(), words.join(" "));
+        }
+        _ => anyhow::bail!("Missing \"=\"."),
+    }
+    Ok(())
+}
+
+pub fn unalias<T: AsRef<str>, TS: AsRef<[T]>>(args: TS, ns: &mut NameSpace) -> anyhow::Result<()> {
+    for name in args.as_ref() {
+        if !ns.remove_alias(name) {
+            anyhow::bail!("Alias \"{}\" is not defined.", name.as_ref());
+        }
+    }
+    Ok(())
+}
+
+pub fn type_<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &NameSpace,
+) -> anyhow::Result<()> {
+    let mut all = false;
+    let mut missing = Vec::new();
+    for name in args.as_ref().iter().map(AsRef::as_ref) {
+        if name == "-a" {
+            all = true;
+            continue;
+        }
+
+        let mut found = Vec::new();
+        if let Some(words) = ns.aliases().get(name) {
+            found.push(format!("{} is an alias for {}", name, words.join(" ")));
+        }
+        if let Some(proc) = ns.get_proc(name) {
+            found.push(format!("{} is a proc:\n{}", name, proc.describe(name)));
+        }
+        if !name.is_empty() && BuiltinKind::new(name).is_some() {
+            found.push(format!("{} is a builtin", name));
+        }
+        if all || found.is_empty() {
+            let exes = if name.contains('/') {
+                Some(PathBuf::from(name))
+                    .filter(|exe| executable(exe))
+                    .into_iter()
+                    .collect()
+            } else if all {
+                search(name, &ns.path())
+            } else {
+                ns.peek_cmd(name).into_iter().collect()
+            };
+            for exe in exes {
+                found.push(format!("{} is {}", name, exe.display()));
+            }
+        }
+
+        match found.as_slice() {
+            [] => missing.push(name),
+            [first, ..] if !all => writeln!(io.stdout(), "{}", first)?,
+            found => {
+                for line in found {
+                    writeln!(io.stdout(), "{}", line)?;
+                }
+            }
+        }
+    }
+
+    match missing.as_slice() {
+        [] => Ok(()),
+        [name] => anyhow::bail!("\"{}\" is not found.", name),
+        names => anyhow::bail!("\"{}\" are not found.", names.join("\", \"")),
+    }
+}
+
+pub fn hash<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &NameSpace,
+) -> anyhow::Result<()> {
+    let mut forget = false;
+    let mut names = Vec::new();
+    for arg in args.as_ref().iter().map(AsRef::as_ref) {
+        match arg {
+            "-r" => ns.clear_hash(),
+            "-d" => forget = true,
+            name => names.push(name),
+        }
+    }
+
+    if names.is_empty() && !forget {
+        for (name, exe, hits) in ns.hashed() {
+            writeln!(io.stdout(), "{:4}  {}  {}", hits, name, exe.display())?;
+        }
+    }
+    for name in names {
+        let found = if forget {
+            ns.forget_cmd(name)
+        } else {
+            ns.hash_cmd(name).is_some()
+        };
+        if !found {
+            anyhow::bail!("\"{}\" is not found.", name);
+        }
+    }
+    Ok(())
+}
+
+pub fn source<T: AsRef<str>, TS: AsRef<[T]>>(args: TS, ns: &mut NameSpace) -> anyhow::Result<()> {
+    use crate::session::{IOReader, Session};
+
+    let mut args = args.as_ref().iter();
+    let name = args.next().context("Specify the file to run.")?;
+
+    let mut session = Session::new(IOReader::new_file(name.as_ref())?)?;
+    session.all_with_args(ns, name, args.map(|arg| Value::from(arg.as_ref())))?;
+    Ok(())
+}
+
+pub fn set<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &mut NameSpace,
+) -> anyhow::Result<()> {
+    let args = args.as_ref();
+    if args.is_empty() {
+        for (name, on) in ns.options().list() {
+            writeln!(io.stdout(), "{:10} {}", name, if on { "on" } else { "off" })?;
+        }
+        return Ok(());
+    }
+
+    let used = ns.options_mut().apply(args)?;
+    if let Some(arg) = args.get(used) {
+        anyhow::bail!("Unexpected argument: \"{}\"", arg.as_ref());
+    }
+    Ok(())
+}
+
+// Like the external `env`, the command is always run as an executable,
+// bypassing procs and builtins of the same name.
+pub fn env(args: &[Value], vars: &mut HashMap<String, String>) -> anyhow::Result<Vec<Value>> {
+    let mut args = args.iter();
+    while let Some(arg) = args.as_slice().first() {
+        let arg = arg.to_string();
+        match arg.as_str() {
+            "-i" | "--ignore-environment" => vars.clear(),
+            "-u" | "--unset" => {
+                args.next();
+                match args.as_slice().first() {
+                    Some(name) => vars.remove(&name.to_string()),
+                    None => anyhow::bail!("Missing the variable name to unset."),
+                };
+            }
+            "--" => {
+                args.next();
+                break;
+            }
+            _ => match arg.split_once('=') {
+                Some((key, value)) if !key.is_empty() => {
+                    vars.insert(String::from(key), String::from(value));
+                }
+                _ => break,
+            },
+        }
+        args.next();
+    }
+    Ok(args.cloned().collect())
+}
+
+pub fn throw(args: &[Value], ns: &NameSpace) -> anyhow::Result<()> {
+    let mut err = match args {
+        [val @ Value::Map(_)] => ShellError::from_value(val)?,
+        _ => {
+            let mut kind = String::from("error");
+            let mut status = 1;
+            let mut message = Vec::new();
+            let mut args = args.iter().map(|arg| arg.to_string());
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-k" => kind = args.next().context("Specify the error kind.")?,
+                    "-s" => {
+                        status = args
+                            .next()
+                            .context("Specify the exit status.")?
+                            .parse()
+                            .context("Failed to parse a number.")?
+                    }
+                    _ => message.push(arg),
+                }
+            }
+            ShellError::new(kind, message.join(" "), status)
+        }
+    };
+
+    if err.location.is_none() {
+        err.location = ns.location();
+    }
+    Err(err.into())
+}
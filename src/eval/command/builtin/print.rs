@@ -0,0 +1,177 @@
+use super::Io;
+use crate::eval::Value;
+use crate::parse::Type;
+use anyhow::Context;
+
+pub fn echo(args: &[Value], io: &mut Io) -> anyhow::Result<()> {
+    let mut newline = true;
+    let mut pretty = false;
+    let mut args = args;
+    while let Some((arg, rest)) = args.split_first() {
+        match arg {
+            Value::Str(opt) if opt == "-n" => newline = false,
+            Value::Str(opt) if opt == "-p" => pretty = true,
+            _ => break,
+        }
+        args = rest;
+    }
+
+    let (sep, text) = if pretty {
+        ("\n", args.iter().map(Value::pretty).collect::<Vec<_>>())
+    } else {
+        (" ", args.iter().map(Value::to_string).collect())
+    };
+    let mut text = text.join(sep);
+    if newline {
+        text.push('\n');
+    }
+    io.stdout().write_all(text.as_bytes())?;
+    io.stdout().flush()?;
+    Ok(())
+}
+
+pub fn printf(args: &[Value], io: &mut Io) -> anyhow::Result<()> {
+    let (format, args) = args.split_first().context("Specify the format.")?;
+    let format = format.to_string();
+
+    let mut args = args.iter();
+    let mut out = String::new();
+    loop {
+        let used = format_once(&format, &mut args, &mut out)?;
+        if !used || args.as_slice().is_empty() {
+            break;
+        }
+    }
+    io.stdout().write_all(out.as_bytes())?;
+    io.stdout().flush()?;
+    Ok(())
+}
+
+fn format_once<'a, I>(format: &str, args: &mut I, out: &mut String) -> anyhow::Result<bool>
+where
+    I: Iterator<Item = &'a Value>,
+{
+    let mut used = false;
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('r') => out.push('\r'),
+                Some('e') => out.push('\x1b'),
+                Some('\\') => out.push('\\'),
+                Some(c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => out.push('\\'),
+            },
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                out.push('%');
+            }
+            '%' => {
+                let mut spec = Spec::default();
+                while let Some(flag) = chars.next_if(|c| *c == '-' || *c == '0') {
+                    match flag {
+                        '-' => spec.left = true,
+                        _ => spec.zero = true,
+                    }
+                }
+                spec.width = number(&mut chars);
+                if chars.next_if_eq(&'.').is_some() {
+                    spec.precision = Some(number(&mut chars).unwrap_or(0));
+                }
+                let conv = chars.next().context("The format ends in a conversion.")?;
+                out.push_str(&spec.convert(conv, args.next())?);
+                used = true;
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(used)
+}
+
+fn number<I: Iterator<Item = char>>(chars: &mut std::iter::Peekable<I>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits.parse().ok()
+}
+
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn convert(&self, conv: char, arg: Option<&Value>) -> anyhow::Result<String> {
+        let arg = arg.cloned().unwrap_or(Value::Null);
+        let (sign, body) = match conv {
+            's' => {
+                let s = arg.to_string();
+                let s = match self.precision {
+                    Some(n) => s.chars().take(n).collect(),
+                    None => s,
+                };
+                return Ok(self.pad("", s, false));
+            }
+            'v' => return Ok(self.pad("", arg.pretty(), false)),
+            'd' => {
+                let n = int(arg)?;
+                let sign = if n < 0 { "-" } else { "" };
+                (sign, self.digits(n.unsigned_abs().to_string()))
+            }
+            'x' => ("", self.digits(format!("{:x}", int(arg)?))),
+            'f' => {
+                let x = match arg {
+                    Value::Null => 0.0,
+                    arg => match arg.cast(Type::Float)? {
+                        Value::Float(x) => x,
+                        _ => unreachable!(),
+                    },
+                };
+                let body = format!("{:.*}", self.precision.unwrap_or(6), x.abs());
+                (if x.is_sign_negative() { "-" } else { "" }, body)
+            }
+            c => anyhow::bail!("Unknown conversion \"%{}\".", c),
+        };
+
+        let zero = conv == 'f' || self.precision.is_none();
+        Ok(self.pad(sign, body, zero))
+    }
+
+    fn digits(&self, digits: String) -> String {
+        match self.precision {
+            Some(n) if digits.len() < n => format!("{}{}", "0".repeat(n - digits.len()), digits),
+            _ => digits,
+        }
+    }
+
+    fn pad(&self, sign: &str, body: String, zero: bool) -> String {
+        let len = sign.chars().count() + body.chars().count();
+        let fill = self.width.unwrap_or(0).saturating_sub(len);
+        if self.left {
+            format!("{}{}{}", sign, body, " ".repeat(fill))
+        } else if self.zero && zero {
+            format!("{}{}{}", sign, "0".repeat(fill), body)
+        } else {
+            format!("{}{}{}", " ".repeat(fill), sign, body)
+        }
+    }
+}
+
+fn int(arg: Value) -> anyhow::Result<i64> {
+    match arg {
+        Value::Null => Ok(0),
+        arg => match arg.cast(Type::Int)? {
+            Value::Int(n) => Ok(n),
+            _ => unreachable!(),
+        },
+    }
+}
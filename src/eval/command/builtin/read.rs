@@ -0,0 +1,203 @@
+extern crate nix;
+
+use super::Io;
+use crate::eval::{NameSpace, Value};
+use crate::job::Status;
+use anyhow::Context;
+use nix::sys::termios::{self, LocalFlags, SetArg, SpecialCharacterIndices, Termios};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+pub fn read<T: AsRef<str>, TS: AsRef<[T]>>(
+    args: TS,
+    io: &mut Io,
+    ns: &mut NameSpace,
+) -> anyhow::Result<Status> {
+    let opts = Options::parse(args.as_ref())?;
+    if opts.list && opts.names.len() > 1 {
+        anyhow::bail!("Only one variable can be given with \"-a\".");
+    }
+
+    let terminal = io.terminal();
+    if let (Some(prompt), true) = (&opts.prompt, terminal) {
+        eprint!("{}", prompt);
+        io::stderr().flush()?;
+    }
+
+    let saved = if terminal && (opts.silent || opts.count.is_some()) {
+        Some(raw(opts.silent, opts.count.is_some())?)
+    } else {
+        None
+    };
+    let res = input(io, &opts);
+    if let Some(saved) = saved {
+        termios::tcsetattr(0, SetArg::TCSANOW, &saved)
+            .context("Failed to restore the terminal attributes.")?;
+    }
+    let (text, end) = res?;
+
+    let names = match opts.names.as_slice() {
+        [] => vec![String::from("REPLY")],
+        names => names.to_vec(),
+    };
+    for name in &names {
+        ns.writable_var(name)?;
+    }
+    if opts.list {
+        let fields = text.split_whitespace().map(Value::from).collect();
+        ns.push_var(&names[0], Value::List(fields));
+    } else if opts.names.is_empty() {
+        ns.push_var(&names[0], Value::from(text));
+    } else {
+        let fields = split(&text, names.len());
+        for (i, name) in names.iter().enumerate() {
+            let field = fields.get(i).copied().unwrap_or_default();
+            ns.push_var(name, Value::from(field));
+        }
+    }
+
+    Ok(Status::Exited(match end {
+        End::Delim => 0,
+        End::Eof => 1,
+        End::Timeout => 2,
+    }))
+}
+
+struct Options {
+    prompt: Option<String>,
+    silent: bool,
+    delim: u8,
+    count: Option<usize>,
+    timeout: Option<Duration>,
+    list: bool,
+    names: Vec<String>,
+}
+
+impl Options {
+    fn parse<T: AsRef<str>>(args: &[T]) -> anyhow::Result<Self> {
+        let mut opts = Self {
+            prompt: None,
+            silent: false,
+            delim: b'\n',
+            count: None,
+            timeout: None,
+            list: false,
+            names: Vec::new(),
+        };
+
+        let mut args = args.iter().map(AsRef::as_ref);
+        while let Some(arg) = args.next() {
+            match arg {
+                "-p" => {
+                    opts.prompt = Some(String::from(args.next().context("Specify the prompt.")?))
+                }
+                "-s" => opts.silent = true,
+                "-a" => opts.list = true,
+                "-d" => {
+                    let delim = args.next().context("Specify the delimiter.")?;
+                    opts.delim = match delim.as_bytes() {
+                        [] => 0,
+                        [c] if c.is_ascii() => *c,
+                        _ => anyhow::bail!("The delimiter must be a single ASCII character."),
+                    };
+                }
+                "-n" => {
+                    let count = args.next().context("Specify the number of characters.")?;
+                    opts.count = Some(count.parse().context("Failed to parse a number.")?);
+                }
+                "-t" => {
+                    let secs = args
+                        .next()
+                        .context("Specify the timeout.")?
+                        .parse::<f64>()
+                        .context("Failed to parse a number.")?;
+                    if !secs.is_finite() || secs < 0.0 {
+                        anyhow::bail!("The timeout must not be negative.");
+                    }
+                    opts.timeout = Some(Duration::from_secs_f64(secs));
+                }
+                "--" => {
+                    opts.names.extend(args.map(String::from));
+                    break;
+                }
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    anyhow::bail!("Unknown option \"{}\".", arg)
+                }
+                _ => opts.names.push(String::from(arg)),
+            }
+        }
+        Ok(opts)
+    }
+}
+
+enum End {
+    Delim,
+    Eof,
+    Timeout,
+}
+
+fn input(io: &mut Io, opts: &Options) -> anyhow::Result<(String, End)> {
+    let deadline = opts.timeout.map(|timeout| Instant::now() + timeout);
+    let mut buf = Vec::new();
+    let mut chars = 0;
+    let mut pending = 0;
+    let end = loop {
+        if pending == 0 && opts.count == Some(chars) {
+            break End::Delim;
+        }
+        let byte = match io.read_byte(deadline) {
+            Ok(Some(byte)) => byte,
+            Ok(None) => break End::Eof,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => break End::Timeout,
+            Err(e) => return Err(anyhow::Error::new(e).context("Failed to read the input.")),
+        };
+
+        if pending > 0 {
+            pending -= 1;
+        } else if byte == opts.delim {
+            break End::Delim;
+        } else {
+            chars += 1;
+            pending = match byte {
+                0xc0..=0xdf => 1,
+                0xe0..=0xef => 2,
+                0xf0..=0xf7 => 3,
+                _ => 0,
+            };
+        }
+        buf.push(byte);
+    };
+    Ok((String::from_utf8_lossy(&buf).into_owned(), end))
+}
+
+fn split(text: &str, n: usize) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut rest = text.trim();
+    while fields.len() + 1 < n {
+        match rest.split_once(char::is_whitespace) {
+            Some((head, tail)) => {
+                fields.push(head);
+                rest = tail.trim_start();
+            }
+            None => break,
+        }
+    }
+    fields.push(rest);
+    fields
+}
+
+fn raw(silent: bool, by_char: bool) -> anyhow::Result<Termios> {
+    let saved = termios::tcgetattr(0).context("Failed to get the terminal attributes.")?;
+    let mut attrs = saved.clone();
+    if silent {
+        attrs.local_flags.remove(LocalFlags::ECHO);
+    }
+    if by_char {
+        attrs.local_flags.remove(LocalFlags::ICANON);
+        attrs.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        attrs.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+    }
+    termios::tcsetattr(0, SetArg::TCSANOW, &attrs)
+        .context("Failed to set the terminal attributes.")?;
+    Ok(saved)
+}
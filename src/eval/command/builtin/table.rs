@@ -0,0 +1,174 @@
+use crate::eval::Value;
+use anyhow::Context;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+pub fn ls(args: &[Value]) -> anyhow::Result<Value> {
+    let mut all = false;
+    let mut paths = Vec::new();
+    for arg in args.iter().map(|arg| arg.to_string()) {
+        match arg.as_str() {
+            "-a" => all = true,
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(String::from("."));
+    }
+
+    let mut res = Vec::new();
+    for path in paths.iter() {
+        let meta = fs::symlink_metadata(path)
+            .with_context(|| format!("Failed to access \"{}\".", path))?;
+        if !meta.is_dir() {
+            res.push(entry(path, &meta));
+            continue;
+        }
+
+        let mut entries = Vec::new();
+        for ent in fs::read_dir(path).with_context(|| format!("Failed to read \"{}\".", path))? {
+            let ent = ent?;
+            let name = ent.file_name().to_string_lossy().into_owned();
+            if !all && name.starts_with('.') {
+                continue;
+            }
+            let name = match paths.len() {
+                1 => name,
+                _ => Path::new(path).join(name).to_string_lossy().into_owned(),
+            };
+            entries.push((name, ent.metadata()?));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        res.extend(entries.iter().map(|(name, meta)| entry(name, meta)));
+    }
+
+    Ok(Value::List(res))
+}
+
+fn entry(name: &str, meta: &fs::Metadata) -> Value {
+    let kind = if meta.file_type().is_symlink() {
+        "symlink"
+    } else if meta.is_dir() {
+        "dir"
+    } else {
+        "file"
+    };
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |dur| dur.as_secs() as i64);
+
+    Value::Map(vec![
+        (String::from("name"), Value::from(name)),
+        (String::from("type"), Value::from(kind)),
+        (String::from("size"), Value::Int(meta.len() as i64)),
+        (String::from("modified"), Value::Int(modified)),
+    ])
+}
+
+// The symbolic operators must be quoted, as they would parse as a redirect or
+// an expansion.
+pub fn where_(args: &[Value], input: Value) -> anyhow::Result<Value> {
+    if args.len() != 3 {
+        anyhow::bail!("Usage: where <field> <operator> <value>");
+    }
+
+    let key = args[0].to_string();
+    let op = args[1].to_string();
+    let rhs = &args[2];
+
+    let mut res = Vec::new();
+    for item in rows(input)? {
+        let lhs = column(&item, &key)?;
+        let matched = match op.as_str() {
+            "==" | "-eq" => lhs.compare(rhs) == Ordering::Equal,
+            "!=" | "-ne" => lhs.compare(rhs) != Ordering::Equal,
+            "<" | "-lt" => lhs.compare(rhs) == Ordering::Less,
+            "<=" | "-le" => lhs.compare(rhs) != Ordering::Greater,
+            ">" | "-gt" => lhs.compare(rhs) == Ordering::Greater,
+            ">=" | "-ge" => lhs.compare(rhs) != Ordering::Less,
+            "=~" => lhs.to_string().contains(&rhs.to_string()),
+            "!~" => !lhs.to_string().contains(&rhs.to_string()),
+            _ => anyhow::bail!("Unknown operator: \"{}\"", op),
+        };
+        if matched {
+            res.push(item);
+        }
+    }
+
+    Ok(Value::List(res))
+}
+
+pub fn select(args: &[Value], input: Value) -> anyhow::Result<Value> {
+    let keys = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    Ok(Value::List(
+        rows(input)?
+            .into_iter()
+            .map(|item| {
+                Ok(Value::Map(
+                    keys.iter()
+                        .map(|key| Ok((key.clone(), column(&item, key)?)))
+                        .collect::<anyhow::Result<_>>()?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?,
+    ))
+}
+
+pub fn sort_by(args: &[Value], input: Value) -> anyhow::Result<Value> {
+    let mut reverse = false;
+    let mut keys = Vec::new();
+    for arg in args.iter().map(|arg| arg.to_string()) {
+        match arg.as_str() {
+            "-r" => reverse = true,
+            _ => keys.push(arg),
+        }
+    }
+    if keys.is_empty() {
+        keys.push(String::from("it"));
+    }
+
+    let mut items = rows(input)?
+        .into_iter()
+        .map(|item| {
+            let cols = keys
+                .iter()
+                .map(|key| column(&item, key))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((cols, item))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    items.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| a.compare(b))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+    if reverse {
+        items.reverse();
+    }
+
+    Ok(Value::List(
+        items.into_iter().map(|(_, item)| item).collect(),
+    ))
+}
+
+fn rows(input: Value) -> anyhow::Result<Vec<Value>> {
+    match input {
+        Value::List(items) => Ok(items),
+        map @ Value::Map(_) => Ok(vec![map]),
+        _ => anyhow::bail!("Expected a list of records."),
+    }
+}
+
+fn column(item: &Value, key: &str) -> anyhow::Result<Value> {
+    match key {
+        "it" => Ok(item.clone()),
+        _ => item.field(key),
+    }
+}
@@ -1,12 +1,23 @@
-use super::Redirects;
+use super::{builtin, returned, trace, Builtin, BuiltinKind, Io, Redirects, Stdin};
 
-use crate::job::SharedJobs;
+use crate::eval::namespace::commands;
+use crate::eval::{NameSpace, ShellError, Value};
+use crate::job::{SharedJobs, Signal, Status};
 use crate::parse::{Arg as ParseArg, Command as ParseCmd, SpecialStr};
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::OwnedFd;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
 use std::process::{Child, Command, Stdio};
+use std::thread;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct External {
+    pub env: Vec<(String, SpecialStr)>,
     pub name: SpecialStr,
     pub args: Args,
     pub reds: Redirects,
@@ -18,22 +29,57 @@ pub struct External {
 pub struct Args(Vec<Arg>);
 
 impl Args {
-    pub fn eval(&self, jobs: &SharedJobs) -> anyhow::Result<Vec<String>> {
+    pub fn eval(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Vec<Value>> {
         let mut res = Vec::new();
         for arg in self.0.iter() {
             match arg {
-                Arg::Normal(s) => res.push(s.eval(jobs)?),
-                Arg::Expand(s) => {
-                    for i in s.eval(jobs)?.split_whitespace() {
-                        res.push(i.to_string());
+                Arg::Normal(s) => res.push(s.eval_value(jobs, ns)?),
+                Arg::Expand(s) => match s.eval_value(jobs, ns)? {
+                    Value::List(items) => res.extend(items),
+                    map @ Value::Map(_) => res.extend(map.keys()),
+                    s => {
+                        for i in s.to_string().split_whitespace() {
+                            res.push(Value::from(i));
+                        }
                     }
-                }
+                },
             }
         }
         Ok(res)
     }
 }
 
+impl fmt::Display for External {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>();
+        if !self.assigns_only() {
+            words.push(self.name.to_string());
+        }
+        write!(f, "{}", words.join(" "))?;
+        for arg in self.args.0.iter() {
+            match arg {
+                Arg::Normal(s) => write!(f, " {}", s)?,
+                Arg::Expand(s) => write!(f, " !{}", s)?,
+            }
+        }
+        let reds = self.reds.to_string();
+        if !reds.is_empty() {
+            write!(f, " {}", reds)?;
+        }
+        if let Some(pipe) = &self.pipe {
+            write!(f, " | {}", pipe)?;
+        }
+        if self.bg {
+            write!(f, " &")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Arg {
     Normal(SpecialStr),
@@ -43,6 +89,7 @@ enum Arg {
 impl From<ParseCmd> for External {
     fn from(cmd: ParseCmd) -> External {
         let ParseCmd {
+            env,
             name,
             args: arg_reds,
             pipe,
@@ -53,10 +100,10 @@ impl From<ParseCmd> for External {
         let mut reds = Vec::new();
         for arg in arg_reds {
             match arg {
-                ParseArg::Arg(s) => {
+                ParseArg::Normal(s) => {
                     args.push(Arg::Normal(s));
                 }
-                ParseArg::ExpandArg(s) => {
+                ParseArg::Expand(s) => {
                     args.push(Arg::Expand(s));
                 }
                 ParseArg::Redirect(r) => {
@@ -69,6 +116,7 @@ impl From<ParseCmd> for External {
         let reds = Redirects::new(reds);
         let pipe = pipe.map(|pipe| Box::new(Self::from(*pipe)));
         Self {
+            env,
             name,
             args,
             reds,
@@ -78,69 +126,441 @@ impl From<ParseCmd> for External {
     }
 }
 
+enum Flow {
+    Empty,
+    Value(Value),
+    Text(String),
+    Child(Child),
+}
+
+enum Stage {
+    Child(Child),
+    Done(Status),
+}
+
+const NOT_FOUND_HOOK: &str = "command_not_found";
+
+impl Flow {
+    fn into_value(self) -> anyhow::Result<Option<Value>> {
+        match self {
+            Self::Empty => Ok(None),
+            Self::Value(val) => Ok(Some(val)),
+            Self::Text(text) => Ok(Some(Value::from_text(text))),
+            Self::Child(child) => {
+                let output = child.wait_with_output()?;
+                Ok(Some(Value::from_text(String::from_utf8(output.stdout)?)))
+            }
+        }
+    }
+}
+
 impl External {
-    pub fn eval(&self, jobs: &SharedJobs) -> anyhow::Result<()> {
-        let child = self.child(jobs, false)?;
-        jobs.with(|jobs| {
-            if self.bg {
-                let (id, pid) = jobs.new_bg(child.id() as i32)?;
-                println!("Job %{} ({}) has started.", id, pid);
-            } else {
-                jobs.new_fg(child.id() as i32)?;
+    pub fn eval(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<Option<Status>> {
+        let mut upstream = Vec::new();
+        let flow = self.run(Flow::Empty, jobs, ns, false, &mut upstream)?;
+        match flow {
+            Flow::Child(child) => {
+                jobs.with(|jobs| {
+                    if self.bg {
+                        let (id, pid) = jobs.new_bg(child.id() as i32)?;
+                        println!("Job %{} ({}) has started.", id, pid);
+                    } else {
+                        jobs.new_fg(child.id() as i32)?;
+                    }
+                    Ok(())
+                })?;
+
+                if self.bg || !ns.options().pipefail {
+                    return Ok(None);
+                }
+                failed(upstream)
+            }
+            flow => {
+                match flow {
+                    Flow::Value(val) => {
+                        let text = val.render();
+                        if !text.is_empty() {
+                            println!("{}", text);
+                        }
+                    }
+                    Flow::Text(text) => print!("{}", text),
+                    _ => (),
+                }
+
+                let last = match upstream.last() {
+                    Some(Stage::Done(status)) => *status,
+                    _ => Status::Exited(0),
+                };
+                Ok(Some(match failed(upstream)? {
+                    Some(failed) if ns.options().pipefail => failed,
+                    _ => last,
+                }))
             }
-            Ok(())
-        })
+        }
     }
 
-    pub fn output(&self, jobs: &SharedJobs) -> anyhow::Result<String> {
-        let child = self.child(jobs, true)?;
-        let output = child.wait_with_output()?;
-        Ok(String::from_utf8(output.stdout)?)
+    pub fn output(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Value> {
+        let mut upstream = Vec::new();
+        let val = match self.run(Flow::Empty, jobs, &mut ns.clone(), true, &mut upstream)? {
+            Flow::Child(child) => {
+                let output = child.wait_with_output()?;
+                Value::from(String::from_utf8(output.stdout)?.trim())
+            }
+            Flow::Value(val) => val,
+            Flow::Text(text) => Value::from(text.trim()),
+            Flow::Empty => Value::default(),
+        };
+        failed(upstream)?;
+        Ok(val)
     }
 
-    fn child(&self, jobs: &SharedJobs, output: bool) -> anyhow::Result<Child> {
-        let mut child = Command::new(&self.name.eval(jobs)?);
-        child.args(&self.args.eval(jobs)?);
+    pub fn assigns_only(&self) -> bool {
+        self.name == SpecialStr::new()
+    }
 
-        let heredoc = self
-            .reds
-            .redirect(&mut child, jobs, false, output || self.pipe.is_some())?;
+    pub fn assigns(
+        &self,
+        jobs: &SharedJobs,
+        ns: &NameSpace,
+    ) -> anyhow::Result<Vec<(String, Value)>> {
+        let mut res = Vec::new();
+        for (key, value) in self.env.iter() {
+            res.push((key.clone(), value.eval_value(jobs, ns)?));
+        }
+        Ok(res)
+    }
 
-        let mut child = child.spawn()?;
+    fn run(
+        &self,
+        input: Flow,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+        output: bool,
+        upstream: &mut Vec<Stage>,
+    ) -> anyhow::Result<Flow> {
+        if self.assigns_only() {
+            anyhow::bail!("Assignments without a command can't be piped or substituted.");
+        }
 
-        if let Some(s) = heredoc {
-            use std::io::Write;
-            child.stdin.take().unwrap().write_all(&s)?;
+        let name = self.name.eval(jobs, ns)?;
+        let args = self.args.eval(jobs, ns)?;
+        let (mut name, mut args) = ns.expand_alias(name, args);
+        let mut env = ns.env().clone();
+        for (key, value) in self.assigns(jobs, ns)? {
+            ns.writable_var(&key)?;
+            env.insert(key, value.to_string());
         }
 
-        if let Some(pipe) = &self.pipe {
-            pipe.pipe_from(child, jobs, output)
+        let kind = BuiltinKind::new(&name).filter(BuiltinKind::structured);
+        let via_env = kind == Some(BuiltinKind::Env);
+        // `env` finds the program with the shell's `PATH`, even after `-i`,
+        // unless its arguments set another.
+        let path = env.get("PATH").cloned();
+        if via_env {
+            args = builtin::env(&args, &mut env)?;
+            if !args.is_empty() {
+                name = args.remove(0).to_string();
+            }
+        }
+
+        let reds = self.reds.expand(jobs, ns)?;
+        trace(ns, &name, &args, &reds.describe());
+
+        let path = env.get("PATH").cloned().or(path).unwrap_or_default();
+        let flow = match BuiltinKind::new(&name).filter(|kind| kind.accepts(&args)) {
+            Some(BuiltinKind::Env) => {
+                let mut vars = env.into_iter().collect::<Vec<_>>();
+                vars.sort();
+                let text = vars
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}\n", key, value))
+                    .collect::<String>();
+                upstream.push(Stage::Done(Status::Exited(0)));
+                let file = match &self.pipe {
+                    Some(_) => None,
+                    None => reds.stdout(jobs, ns)?,
+                };
+                match file {
+                    Some(mut file) => {
+                        file.write_all(text.as_bytes())?;
+                        Flow::Empty
+                    }
+                    None => Flow::Text(text),
+                }
+            }
+            Some(kind) if kind.structured() => {
+                let input = match input {
+                    Flow::Empty if kind == BuiltinKind::From && args.len() == 1 => {
+                        Some(Value::from_text(stdin(&reds, jobs, ns)?))
+                    }
+                    input => input.into_value()?,
+                };
+                let val = Builtin::new(kind, args).eval_value(input)?;
+                upstream.push(Stage::Done(Status::Exited(0)));
+                let file = match &self.pipe {
+                    Some(_) => None,
+                    None => reds.stdout(jobs, ns)?,
+                };
+                match file {
+                    Some(mut file) => {
+                        writeln!(file, "{}", val.render())?;
+                        Flow::Empty
+                    }
+                    None => Flow::Value(val),
+                }
+            }
+            Some(kind) if !via_env => {
+                let builtin = Builtin::new(kind, args);
+                let capture = output || self.pipe.is_some();
+                run_builtin(builtin, &reds, input, jobs, ns, capture, upstream)?
+            }
+            _ if !via_env && hooked(&name, &path, ns) => {
+                args.insert(0, Value::from(name.as_str()));
+                self.hook(args, input, jobs, ns, output, upstream)?
+            }
+            _ => {
+                let mut child = match ns.lookup_cmd(&name, &path) {
+                    Some(exe) => {
+                        let mut child = Command::new(exe);
+                        child.arg0(&name);
+                        child
+                    }
+                    None if name.contains('/') => Command::new(&name),
+                    None => {
+                        let ns = Some(&*ns).filter(|_| !via_env);
+                        return Err(not_found(&name, &path, ns).into());
+                    }
+                };
+                child.args(args.iter().map(|arg| arg.to_string()));
+                child.env_clear().envs(env);
+                let piped_in = !matches!(input, Flow::Empty);
+                let piped_out = output || self.pipe.is_some();
+                let heredoc = reds.redirect(&mut child, jobs, ns, piped_in, piped_out)?;
+                Flow::Child(spawn(child, heredoc, input, upstream)?)
+            }
+        };
+
+        match &self.pipe {
+            Some(pipe) => pipe.run(flow, jobs, ns, output, upstream),
+            None => Ok(flow),
+        }
+    }
+
+    fn hook(
+        &self,
+        args: Vec<Value>,
+        input: Flow,
+        jobs: &SharedJobs,
+        ns: &NameSpace,
+        output: bool,
+        upstream: &mut Vec<Stage>,
+    ) -> anyhow::Result<Flow> {
+        if let Flow::Child(other) = input {
+            upstream.push(Stage::Child(other));
+        }
+        let mut ns = ns.clone();
+        ns.set_hooked(true);
+        let proc = ns.get_proc(NOT_FOUND_HOOK).unwrap();
+        let (val, flow) = if output || self.pipe.is_some() {
+            let (res, text) =
+                Io::capture_stdout(|| proc.call(NOT_FOUND_HOOK, args, jobs, &mut ns))?;
+            match res? {
+                Some(val) if output && self.pipe.is_none() => (Some(val.clone()), Flow::Value(val)),
+                val => (val, Flow::Text(text)),
+            }
         } else {
-            Ok(child)
+            (proc.call(NOT_FOUND_HOOK, args, jobs, &mut ns)?, Flow::Empty)
+        };
+        upstream.push(Stage::Done(returned(val)));
+        Ok(flow)
+    }
+}
+
+fn hooked(name: &str, path: &str, ns: &NameSpace) -> bool {
+    !ns.hooked()
+        && !name.contains('/')
+        && ns.get_proc(NOT_FOUND_HOOK).is_some()
+        && ns.get_proc(name).is_none()
+        && ns.lookup_cmd(name, path).is_none()
+}
+
+fn failed(stages: Vec<Stage>) -> anyhow::Result<Option<Status>> {
+    let mut failed = None;
+    for stage in stages {
+        let status = match stage {
+            Stage::Child(mut child) => {
+                let status = child.wait()?;
+                match (status.code(), status.signal()) {
+                    (Some(code), _) => Some(Status::Exited(code)),
+                    (None, Some(sig)) => Signal::try_from(sig).ok().map(Status::Signaled),
+                    (None, None) => None,
+                }
+            }
+            Stage::Done(status) => Some(status),
+        };
+        failed = match status {
+            Some(Status::Exited(0)) | None => failed,
+            status => status,
+        };
+    }
+    Ok(failed)
+}
+
+// Only a captured builtin runs on a copy of the namespace, so that the last
+// stage of `cmd | read x` sets `x`.
+fn run_builtin(
+    builtin: Builtin,
+    reds: &Redirects,
+    input: Flow,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+    capture: bool,
+    upstream: &mut Vec<Stage>,
+) -> anyhow::Result<Flow> {
+    let mut copy;
+    let (ns, mut io) = if capture {
+        copy = ns.clone();
+        let io = Io::capture(reds, jobs, &copy)?;
+        (&mut copy, io)
+    } else {
+        let io = Io::new(reds, jobs, ns)?;
+        (ns, io)
+    };
+
+    let mut other = None;
+    match input {
+        Flow::Empty => (),
+        Flow::Value(val) => io.pipe(Stdin::HereDoc(format!("{}\n", val.render()).into_bytes())),
+        Flow::Text(text) => io.pipe(Stdin::HereDoc(text.into_bytes())),
+        Flow::Child(mut child) => {
+            if let Some(stdout) = child.stdout.take() {
+                io.pipe(Stdin::File(File::from(OwnedFd::from(stdout))));
+            }
+            other = Some(child);
         }
     }
 
-    fn pipe_from(&self, other: Child, jobs: &SharedJobs, output: bool) -> anyhow::Result<Child> {
-        let mut child = Command::new(&self.name.eval(jobs)?);
-        child.args(&self.args.eval(jobs)?);
+    // A builtin on a copy of the namespace can't keep a change of directory,
+    // as `PWD` and `OLDPWD` would not follow it.
+    let cwd = std::env::current_dir().ok().filter(|_| capture);
+    let status = builtin.eval(&mut io, jobs, ns);
+    if let Some(cwd) = cwd {
+        std::env::set_current_dir(cwd)?;
+    }
+    // Closes the pipe before the previous stage is waited for, so that it
+    // can't block writing what the builtin didn't read.
+    let text = io.captured();
+    if let Some(other) = other {
+        upstream.push(Stage::Child(other));
+    }
+    upstream.push(Stage::Done(status?));
+    Ok(if capture {
+        Flow::Text(text)
+    } else {
+        Flow::Empty
+    })
+}
+
+fn spawn(
+    mut child: Command,
+    heredoc: Option<Vec<u8>>,
+    input: Flow,
+    upstream: &mut Vec<Stage>,
+) -> anyhow::Result<Child> {
+    let input = match input {
+        Flow::Empty => heredoc,
+        Flow::Value(val) => Some(format!("{}\n", val.render()).into_bytes()),
+        Flow::Text(text) => Some(text.into_bytes()),
+        Flow::Child(mut other) => {
+            child.stdin(Stdio::from(other.stdout.take().unwrap()));
+            upstream.push(Stage::Child(other));
+            None
+        }
+    };
+
+    let mut child = match child.spawn() {
+        Ok(child) => child,
+        Err(e) => return Err(spawn_error(&child.get_program().to_string_lossy(), e)),
+    };
 
-        let heredoc = self
-            .reds
-            .redirect(&mut child, jobs, true, output || self.pipe.is_some())?;
+    if let Some(s) = input {
+        let mut stdin = child.stdin.take().unwrap();
+        thread::spawn(move || stdin.write_all(&s));
+    }
 
-        child.stdin(Stdio::from(other.stdout.unwrap()));
+    Ok(child)
+}
 
-        let mut child = child.spawn()?;
+fn stdin(reds: &Redirects, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<String> {
+    let mut text = String::new();
+    match reds.stdin(jobs, ns)? {
+        Some(Stdin::File(mut file)) => file.read_to_string(&mut text)?,
+        Some(Stdin::HereDoc(doc)) => return Ok(String::from_utf8(doc)?),
+        None => io::stdin().read_to_string(&mut text)?,
+    };
+    Ok(text)
+}
 
-        if let Some(s) = heredoc {
-            use std::io::Write;
-            child.stdin.take().unwrap().write_all(&s)?;
+fn spawn_error(name: &str, err: io::Error) -> anyhow::Error {
+    match err.kind() {
+        io::ErrorKind::NotFound => {
+            ShellError::new("notfound", format!("command not found: {}", name), 127).into()
+        }
+        io::ErrorKind::PermissionDenied if Path::new(name).is_dir() => {
+            ShellError::new("noexec", format!("is a directory: {}", name), 126).into()
+        }
+        io::ErrorKind::PermissionDenied => {
+            ShellError::new("noexec", format!("permission denied: {}", name), 126).into()
         }
+        _ => anyhow::Error::new(err).context(format!("Failed to run \"{}\".", name)),
+    }
+}
 
-        if let Some(pipe) = &self.pipe {
-            pipe.pipe_from(child, jobs, output)
-        } else {
-            Ok(child)
+fn not_found(name: &str, path: &str, ns: Option<&NameSpace>) -> ShellError {
+    let mut names = commands(path);
+    if let Some(ns) = ns {
+        names.extend(ns.proc_names());
+        names.extend(ns.aliases().keys().cloned());
+        names.extend(BuiltinKind::NAMES.iter().map(|name| String::from(*name)));
+    }
+
+    let limit = (name.chars().count() / 3).max(1);
+    let mut close = names
+        .into_iter()
+        .map(|other| (distance(name, &other), other))
+        .filter(|(dist, _)| (1..=limit).contains(dist))
+        .collect::<Vec<_>>();
+    close.sort();
+    close.dedup();
+
+    let mut message = format!("command not found: {}", name);
+    let close = close
+        .into_iter()
+        .take(3)
+        .map(|(_, other)| format!("\"{}\"", other))
+        .collect::<Vec<_>>();
+    if !close.is_empty() {
+        message.push_str(&format!(" (did you mean {}?)", close.join(", ")));
+    }
+    ShellError::new("notfound", message, 127)
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
         }
+        rows.push(row);
     }
+    rows[a.len()][b.len()]
 }
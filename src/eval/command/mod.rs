@@ -2,13 +2,14 @@ mod builtin;
 mod external;
 mod redirect;
 
-pub use builtin::{Builtin, BuiltinKind};
+pub use builtin::{Builtin, BuiltinKind, Io};
 pub use external::External;
-pub use redirect::Redirects;
+pub use redirect::{Redirects, Stdin};
 
-use super::NameSpace;
-use crate::job::SharedJobs;
-use crate::parse::Command as ParseCmd;
+use super::{NameSpace, Value};
+use crate::job::{SharedJobs, Status};
+use crate::parse::{Command as ParseCmd, Type};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Command(External);
@@ -19,23 +20,118 @@ impl From<ParseCmd> for Command {
     }
 }
 
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Command {
-    pub fn eval(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<()> {
-        let name = self.0.name.eval(jobs)?;
-        let proc = ns.get_proc(&name);
-        if let Some(proc) = proc {
-            return proc.eval_with_args(&name, self.0.args.eval(jobs)?, jobs, ns);
+    pub fn eval(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<Option<Status>> {
+        if self.0.assigns_only() && self.0.pipe.is_none() {
+            return self.assign(jobs, ns);
+        }
+
+        let name = self.0.name.eval(jobs, ns)?;
+        let (name, mut args) = ns.expand_alias(name, Vec::new());
+
+        let kind = BuiltinKind::new(&name).filter(|kind| !kind.structured());
+        if ns.get_proc(&name).is_none() && (kind.is_none() || self.0.pipe.is_some()) {
+            return self.0.eval(jobs, ns);
+        }
+
+        args.extend(self.0.args.eval(jobs, ns)?);
+        let assigns = self.0.assigns(jobs, ns)?;
+        if assigns.is_empty() {
+            return self.call(&name, args, jobs, ns);
         }
+        let saved = ns.mark_env(assigns)?;
+        let res = self.call(&name, args, jobs, ns);
+        ns.drop_env(saved);
+        res
+    }
 
-        let kind = BuiltinKind::new(name);
-        if let Some(kind) = kind {
-            return Builtin::new(kind, self.0.args.eval(jobs)?).eval(jobs, ns);
+    fn assign(&self, jobs: &SharedJobs, ns: &mut NameSpace) -> anyhow::Result<Option<Status>> {
+        for (key, value) in self.0.assigns(jobs, ns)? {
+            trace(ns, &format!("{}={}", key, value), &[], &[]);
+            ns.set_var(key, value)?;
         }
+        Ok(Some(Status::Exited(0)))
+    }
 
-        self.0.eval(jobs)
+    fn call(
+        &self,
+        name: &str,
+        args: Vec<Value>,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+    ) -> anyhow::Result<Option<Status>> {
+        if let Some(proc) = ns.get_proc(name) {
+            trace(ns, name, &args, &[]);
+            return Ok(Some(returned(proc.call(name, args, jobs, ns)?)));
+        }
+
+        if let Some(kind) = BuiltinKind::new(name) {
+            let reds = self.0.reds.expand(jobs, ns)?;
+            trace(ns, name, &args, &reds.describe());
+            let mut io = Io::new(&reds, jobs, ns)?;
+            return Ok(Some(Builtin::new(kind, args).eval(&mut io, jobs, ns)?));
+        }
+        Ok(None)
     }
 
-    pub fn output(&self, jobs: &SharedJobs) -> anyhow::Result<String> {
-        self.0.output(jobs)
+    pub fn output(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Value> {
+        let name = self.0.name.eval(jobs, ns)?;
+        let (name, mut args) = ns.expand_alias(name, Vec::new());
+        if let Some(proc) = ns.get_proc(&name) {
+            args.extend(self.0.args.eval(jobs, ns)?);
+            let mut ns = ns.clone();
+            ns.mark_env(self.0.assigns(jobs, &ns)?)?;
+            let (res, text) = Io::capture_stdout(|| proc.call(&name, args, jobs, &mut ns))?;
+            return Ok(res?.unwrap_or_else(|| Value::from(text.trim())));
+        }
+
+        self.0.output(jobs, ns)
+    }
+}
+
+fn returned(val: Option<Value>) -> Status {
+    match val.map(|val| val.cast(Type::Int)) {
+        Some(Ok(Value::Int(code))) => Status::Exited(code as i32),
+        _ => Status::Exited(0),
+    }
+}
+
+fn trace(ns: &NameSpace, name: &str, args: &[Value], reds: &[String]) {
+    if !ns.options().xtrace || name.is_empty() {
+        return;
+    }
+
+    let prefix = ns
+        .get_var("PS4")
+        .map_or(String::from("+ "), |ps4| ps4.to_string());
+    let mut head = prefix
+        .chars()
+        .take(1)
+        .collect::<String>()
+        .repeat(ns.depth());
+    head.push_str(&prefix);
+    if let Some(location) = ns.location() {
+        head.push_str(&location);
+        head.push_str(": ");
+    }
+
+    let line = std::iter::once(quote(name))
+        .chain(args.iter().map(|arg| quote(&arg.to_string())))
+        .chain(reds.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    eprintln!("{}{}", head, line);
+}
+
+fn quote(word: &str) -> String {
+    if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || c == '\'') {
+        return String::from(word);
     }
+    format!("'{}'", word.replace('\'', "'\\''"))
 }
@@ -1,6 +1,9 @@
+use crate::eval::NameSpace;
 use crate::job::SharedJobs;
 use crate::parse::{RedKind, RedTarget, Redirect, SpecialStr};
+use std::fmt;
 use std::fs::{File, OpenOptions};
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -15,29 +18,135 @@ impl Redirects {
         &self,
         cmd: &mut Command,
         jobs: &SharedJobs,
+        ns: &NameSpace,
         piped_in: bool,
         piped_out: bool,
     ) -> anyhow::Result<Option<Vec<u8>>> {
-        self.0.redirect(cmd, jobs, piped_in, piped_out)
+        self.0.redirect(cmd, jobs, ns, piped_in, piped_out)
+    }
+
+    // Tracing and running the command must not evaluate the targets twice.
+    pub fn expand(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Self> {
+        let mut res = self.clone();
+        let (stdin, outs) = match &mut res.0 {
+            RedirectsInner::Bind(stdin, stdout) => (stdin, vec![stdout]),
+            RedirectsInner::Each(stdin, stdout, stderr) => (stdin, vec![stdout, stderr]),
+        };
+        let targets = stdin
+            .iter_mut()
+            .map(|stdin| &mut stdin.target)
+            .chain(outs.into_iter().flatten().map(|out| &mut out.target));
+        for target in targets {
+            if let RedTarget::Other(s) = target {
+                *s = SpecialStr::from(s.eval(jobs, ns)?);
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        self.ops()
+            .into_iter()
+            .map(|(op, target)| describe(&op, target))
+            .collect()
+    }
+
+    fn ops(&self) -> Vec<(String, &RedTarget)> {
+        let (stdin, stdout, stderr, both) = match &self.0 {
+            RedirectsInner::Bind(stdin, stdout) => (stdin, &None, &None, stdout),
+            RedirectsInner::Each(stdin, stdout, stderr) => (stdin, stdout, stderr, &None),
+        };
+
+        let mut res = Vec::new();
+        if let Some(stdin) = stdin {
+            let op = match stdin.mode {
+                InMode::Normal => "<",
+                InMode::HereDoc => "<<",
+            };
+            res.push((String::from(op), &stdin.target));
+        }
+        for (fd, out) in [("", stdout), ("2", stderr), ("&", both)] {
+            if let Some(out) = out {
+                let op = match out.mode {
+                    OutMode::Overwrite => ">",
+                    OutMode::Append => ">>",
+                };
+                res.push((format!("{}{}", fd, op), &out.target));
+            }
+        }
+        res
+    }
+
+    pub fn stdin(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Option<Stdin>> {
+        let stdin = match &self.0 {
+            RedirectsInner::Bind(stdin, _) => stdin,
+            RedirectsInner::Each(stdin, _, _) => stdin,
+        };
+
+        let stdin = match stdin {
+            Some(stdin) => stdin,
+            None => return Ok(None),
+        };
+        let target = path(&stdin.target, jobs, ns)?;
+        Ok(Some(match stdin.mode {
+            InMode::Normal => Stdin::File(File::open(&target)?),
+            InMode::HereDoc => Stdin::HereDoc(target.into_bytes()),
+        }))
+    }
+
+    pub fn stdout(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Option<File>> {
+        let stdout = match &self.0 {
+            RedirectsInner::Bind(_, stdout) => stdout,
+            RedirectsInner::Each(_, stdout, _) => stdout,
+        };
+
+        match stdout {
+            Some(stdout) => Ok(Some(stdout.open(jobs, ns)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl fmt::Display for Redirects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (op, target)) in self.ops().into_iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", describe(&op, target))?;
+        }
+        Ok(())
     }
 }
 
+pub enum Stdin {
+    File(File),
+    HereDoc(Vec<u8>),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum RedirectsInner {
     Each(Option<RedIn>, Option<RedOut>, Option<RedOut>),
     Bind(Option<RedIn>, Option<RedOut>),
 }
 
-fn target2str(target: RedTarget) -> SpecialStr {
+fn describe(op: &str, target: &RedTarget) -> String {
     match target {
-        RedTarget::Stdin => SpecialStr::from(String::from("/dev/stdin")),
-        RedTarget::Stdout => SpecialStr::from(String::from("/dev/stdout")),
-        RedTarget::Stderr => SpecialStr::from(String::from("/dev/stderr")),
-        RedTarget::Null => SpecialStr::from(String::from("/dev/null")),
-        RedTarget::Other(s) => s,
+        RedTarget::Other(s) => format!("{} {}", op, s),
+        target => format!("{}{}", op, target),
     }
 }
 
+fn path(target: &RedTarget, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<String> {
+    Ok(match target {
+        RedTarget::Stdin => String::from("/dev/stdin"),
+        RedTarget::Stdout => String::from("/dev/stdout"),
+        RedTarget::Stderr => String::from("/dev/stderr"),
+        RedTarget::Null => String::from("/dev/null"),
+        RedTarget::Other(s) => s.eval(jobs, ns)?,
+    })
+}
+
 impl RedirectsInner {
     fn new(reds: Vec<Redirect>) -> Self {
         let mut stdout = None;
@@ -68,13 +177,13 @@ impl RedirectsInner {
                 RedKind::Stdin => {
                     stdin = Some(RedIn {
                         mode: InMode::Normal,
-                        target: target2str(red.target),
+                        target: red.target,
                     });
                 }
                 RedKind::HereDoc => {
                     stdin = Some(RedIn {
                         mode: InMode::HereDoc,
-                        target: target2str(red.target),
+                        target: red.target,
                     });
                 }
             }
@@ -91,18 +200,19 @@ impl RedirectsInner {
         &self,
         cmd: &mut Command,
         jobs: &SharedJobs,
+        ns: &NameSpace,
         piped_in: bool,
         piped_out: bool,
     ) -> anyhow::Result<Option<Vec<u8>>> {
         let stdin = match self {
             Self::Bind(stdin, Some(stdout)) if piped_out => {
-                let err = stdout.mode.option().open(&stdout.target.eval(jobs)?)?;
+                let err = stdout.open(jobs, ns)?;
                 cmd.stdout(Stdio::piped());
                 cmd.stderr(Stdio::from(err));
                 stdin
             }
             Self::Bind(stdin, Some(stdout)) => {
-                let out = stdout.mode.option().open(&stdout.target.eval(jobs)?)?;
+                let out = stdout.open(jobs, ns)?;
                 let err = out.try_clone()?;
                 cmd.stdout(Stdio::from(out));
                 cmd.stderr(Stdio::from(err));
@@ -117,12 +227,12 @@ impl RedirectsInner {
                 if piped_out {
                     cmd.stdout(Stdio::piped());
                 } else if let Some(stdout) = stdout {
-                    let out = stdout.mode.option().open(&stdout.target.eval(jobs)?)?;
+                    let out = stdout.open(jobs, ns)?;
                     cmd.stdout(Stdio::from(out));
                 }
 
                 if let Some(stderr) = stderr {
-                    let err = stderr.mode.option().open(&stderr.target.eval(jobs)?)?;
+                    let err = stderr.open(jobs, ns)?;
                     cmd.stderr(Stdio::from(err));
                 }
 
@@ -134,7 +244,7 @@ impl RedirectsInner {
         if piped_in {
             cmd.stdin(Stdio::piped());
         } else if let Some(stdin) = stdin {
-            let target = stdin.target.eval(jobs)?;
+            let target = path(&stdin.target, jobs, ns)?;
             match stdin.mode {
                 InMode::Normal => {
                     cmd.stdin(Stdio::from(File::open(&target)?));
@@ -153,22 +263,33 @@ impl RedirectsInner {
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct RedOut {
     mode: OutMode,
-    target: SpecialStr,
+    target: RedTarget,
 }
 
 impl RedOut {
     fn overwrite(target: RedTarget) -> Self {
         Self {
             mode: OutMode::Overwrite,
-            target: target2str(target),
+            target,
         }
     }
 
     fn append(target: RedTarget) -> Self {
         Self {
             mode: OutMode::Append,
-            target: target2str(target),
+            target,
+        }
+    }
+
+    fn open(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<File> {
+        let target = path(&self.target, jobs, ns)?;
+        let clobber = Path::new(&target)
+            .metadata()
+            .is_ok_and(|meta| meta.is_file());
+        if self.mode == OutMode::Overwrite && clobber && ns.options().noclobber {
+            anyhow::bail!("Can't overwrite the existing file \"{}\".", target);
         }
+        Ok(self.mode.option().open(&target)?)
     }
 }
 
@@ -192,7 +313,7 @@ impl OutMode {
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct RedIn {
     mode: InMode,
-    target: SpecialStr,
+    target: RedTarget,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
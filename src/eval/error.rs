@@ -0,0 +1,71 @@
+use super::Value;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShellError {
+    pub message: String,
+    pub kind: String,
+    pub status: i32,
+    pub location: Option<String>,
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ShellError {}
+
+impl ShellError {
+    pub fn new<T: Into<String>, U: Into<String>>(kind: T, message: U, status: i32) -> Self {
+        Self {
+            message: message.into(),
+            kind: kind.into(),
+            status,
+            location: None,
+        }
+    }
+
+    pub fn from_anyhow(err: &anyhow::Error, location: Option<String>) -> Self {
+        let mut res = if let Some(err) = err.downcast_ref::<Self>() {
+            err.clone()
+        } else if err.downcast_ref::<std::io::Error>().is_some() {
+            Self::new("io", err.to_string(), 1)
+        } else {
+            Self::new("error", err.to_string(), 1)
+        };
+
+        if res.location.is_none() {
+            res.location = location;
+        }
+        res
+    }
+
+    pub fn from_value(val: &Value) -> anyhow::Result<Self> {
+        let field = |key| val.field(key).ok().filter(|val| *val != Value::Null);
+        let message = match field("message") {
+            Some(message) => message.to_string(),
+            None => anyhow::bail!("An error record requires the \"message\" field."),
+        };
+
+        Ok(Self {
+            message,
+            kind: field("kind").map_or(String::from("error"), |kind| kind.to_string()),
+            status: field("status").map_or(Ok(1), |status| status.to_string().parse())?,
+            location: field("location").map(|location| location.to_string()),
+        })
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Map(vec![
+            (String::from("message"), Value::from(&self.message)),
+            (String::from("kind"), Value::from(&self.kind)),
+            (String::from("status"), Value::Int(self.status as i64)),
+            (
+                String::from("location"),
+                self.location.as_ref().map_or(Value::Null, Value::from),
+            ),
+        ])
+    }
+}
@@ -1,7 +1,14 @@
 mod block;
 mod command;
+mod error;
 mod namespace;
+mod parallel;
+mod proc;
+mod value;
 
 pub use block::Block;
 pub use command::Command;
-pub use namespace::NameSpace;
+pub use error::ShellError;
+pub use namespace::{Location, NameSpace};
+pub use proc::Proc;
+pub use value::Value;
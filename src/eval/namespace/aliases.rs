@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Aliases {
+    aliases: HashMap<String, Vec<String>>,
+    abbrs: HashMap<String, String>,
+}
+
+impl Aliases {
+    pub fn push<T: Into<String>>(&mut self, name: T, words: Vec<String>) {
+        self.aliases.insert(name.into(), words);
+    }
+
+    pub fn push_abbr<T: Into<String>, U: Into<String>>(&mut self, name: T, expansion: U) {
+        self.abbrs.insert(name.into(), expansion.into());
+    }
+
+    pub fn remove<T: AsRef<str>>(&mut self, name: T) -> bool {
+        let name = name.as_ref();
+        let alias = self.aliases.remove(name).is_some();
+        let abbr = self.abbrs.remove(name).is_some();
+        alias || abbr
+    }
+
+    // Each alias is expanded only once, so that `alias ls = ls -F` terminates.
+    pub fn expand(&self, mut name: String) -> (String, Vec<String>) {
+        let mut seen = Vec::new();
+        let mut prefix = Vec::new();
+        while let Some(words) = self.aliases.get(&name) {
+            if seen.contains(&name) {
+                break;
+            }
+            let (head, rest) = match words.split_first() {
+                Some(split) => split,
+                None => break,
+            };
+            seen.push(name);
+            prefix.splice(0..0, rest.iter().cloned());
+            name = head.clone();
+        }
+        (name, prefix)
+    }
+
+    pub fn aliases(&self) -> &HashMap<String, Vec<String>> {
+        &self.aliases
+    }
+
+    pub fn abbrs(&self) -> &HashMap<String, String> {
+        &self.abbrs
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Hash {
+    path: String,
+    table: HashMap<String, (PathBuf, usize)>,
+}
+
+impl Hash {
+    pub fn lookup(&mut self, name: &str, path: &str) -> Option<PathBuf> {
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+        self.sync(path);
+        match self.table.get_mut(name) {
+            Some((exe, hits)) if executable(exe) => {
+                *hits += 1;
+                return Some(exe.clone());
+            }
+            _ => (),
+        }
+
+        let exe = search(name, path).into_iter().next()?;
+        self.table.insert(String::from(name), (exe.clone(), 1));
+        Some(exe)
+    }
+
+    pub fn peek(&self, name: &str, path: &str) -> Option<PathBuf> {
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+        match self.table.get(name) {
+            Some((exe, _)) if self.path == path && executable(exe) => Some(exe.clone()),
+            _ => search(name, path).into_iter().next(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, path: &str) -> Option<PathBuf> {
+        let exe = self.lookup(name, path)?;
+        if let Some((_, hits)) = self.table.get_mut(name) {
+            *hits -= 1;
+        }
+        Some(exe)
+    }
+
+    fn sync(&mut self, path: &str) {
+        if self.path != path {
+            self.table.clear();
+            self.path = String::from(path);
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.table.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn entries(&mut self, path: &str) -> Vec<(String, PathBuf, usize)> {
+        self.sync(path);
+        let mut entries = self
+            .table
+            .iter()
+            .map(|(name, (exe, hits))| (name.clone(), exe.clone(), *hits))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+}
+
+pub fn search(name: &str, path: &str) -> Vec<PathBuf> {
+    dirs(path)
+        .map(|dir| Path::new(dir).join(name))
+        .filter(|exe| executable(exe))
+        .collect()
+}
+
+pub fn commands(path: &str) -> Vec<String> {
+    let mut names = dirs(path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| executable(&entry.path()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+// Empty entries are skipped rather than taken as the current directory, so
+// an empty or unset `$PATH` searches nothing.
+fn dirs(path: &str) -> impl Iterator<Item = &str> {
+    path.split(':').filter(|dir| !dir.is_empty())
+}
+
+pub fn executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
@@ -1,43 +1,252 @@
+mod aliases;
+mod hash;
+mod options;
 mod procs;
 mod vars;
 
+use aliases::Aliases;
+use hash::Hash;
+pub use hash::{commands, executable, search};
+pub use options::Options;
 use procs::Procs;
 use vars::Vars;
 
-use crate::eval::Block;
+use crate::eval::{Proc, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    source: String,
+    start: usize,
+    line: usize,
+}
+
+impl Location {
+    pub fn new<T: Into<String>>(source: T, start: usize) -> Self {
+        Self {
+            source: source.into(),
+            start,
+            line: 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.start + self.line - 1)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct NameSpace {
     vars: Vars,
     procs: Procs,
+    aliases: Aliases,
+    hash: Rc<RefCell<Hash>>,
+    options: Options,
+    dirs: Vec<PathBuf>,
+    location: Option<Location>,
+    depth: usize,
+    hooked: bool,
 }
 
 impl NameSpace {
     pub fn set_args<T, U, US>(&mut self, name: T, args: US)
     where
         T: AsRef<str>,
-        U: AsRef<str>,
+        U: Into<Value>,
         US: IntoIterator<Item = U>,
     {
         self.vars.set_args(name, args);
     }
 
-    pub fn push_var<T: Into<String>, U: AsRef<str>>(&mut self, key: T, value: U) {
+    pub fn push_var<T: Into<String>, U: Into<Value>>(&mut self, key: T, value: U) {
         self.vars.push(key, value);
     }
 
-    pub fn push_gvar<T: AsRef<str>, U: AsRef<str>>(&mut self, key: T, value: U) {
+    pub fn set_var<T: Into<String>, U: Into<Value>>(
+        &mut self,
+        key: T,
+        value: U,
+    ) -> anyhow::Result<()> {
+        self.vars.set(key, value)
+    }
+
+    pub fn const_var<T: Into<String>>(&mut self, key: T, value: Value) -> anyhow::Result<()> {
+        self.vars.constant(key, value, self.location())
+    }
+
+    pub fn readonly_var<T: AsRef<str>>(&mut self, key: T) -> anyhow::Result<()> {
+        self.vars.readonly(key, self.location())
+    }
+
+    pub fn mark_env(
+        &mut self,
+        pairs: Vec<(String, Value)>,
+    ) -> anyhow::Result<Vec<(String, Option<String>)>> {
+        self.vars.mark_env(pairs)
+    }
+
+    pub fn drop_env(&mut self, saved: Vec<(String, Option<String>)>) {
+        self.vars.drop_env(saved);
+    }
+
+    pub fn writable_var(&self, key: &str) -> anyhow::Result<()> {
+        self.vars.writable(key)
+    }
+
+    pub fn readonly_names(&self) -> Vec<String> {
+        self.vars.readonly_names()
+    }
+
+    pub fn get_var<T: AsRef<str>>(&self, key: T) -> Option<Value> {
+        self.vars.get(key)
+    }
+
+    pub fn push_gvar<T: Into<String>, U: Into<Value>>(&mut self, key: T, value: U) {
         self.vars.gpush(key, value);
     }
 
-    pub fn push_proc<T: Into<String>>(&mut self, name: T, block: Block) {
-        self.procs.push(name, block);
+    pub fn export_var<T: Into<String>>(&mut self, key: T, value: Value) -> anyhow::Result<()> {
+        self.vars.export(key, value)
     }
 
-    pub fn get_proc<T: AsRef<str>>(&self, name: T) -> Option<Block> {
+    pub fn env(&self) -> &HashMap<String, String> {
+        self.vars.env()
+    }
+
+    pub fn push_proc<T: Into<String>>(&mut self, name: T, proc: Proc) {
+        self.procs.push(name, proc);
+    }
+
+    pub fn get_proc<T: AsRef<str>>(&self, name: T) -> Option<Proc> {
         self.procs.get(name)
     }
 
+    pub fn proc_names(&self) -> Vec<String> {
+        self.procs.names()
+    }
+
+    pub fn push_alias<T: Into<String>>(&mut self, name: T, words: Vec<String>) {
+        self.aliases.push(name, words);
+    }
+
+    pub fn push_abbr<T: Into<String>, U: Into<String>>(&mut self, name: T, expansion: U) {
+        self.aliases.push_abbr(name, expansion);
+    }
+
+    pub fn remove_alias<T: AsRef<str>>(&mut self, name: T) -> bool {
+        self.aliases.remove(name)
+    }
+
+    pub fn expand_alias(&self, name: String, args: Vec<Value>) -> (String, Vec<Value>) {
+        let (name, prefix) = self.aliases.expand(name);
+        let args = prefix.into_iter().map(Value::from).chain(args).collect();
+        (name, args)
+    }
+
+    // The hash table is shared by copies of the namespace, so lookups from
+    // command substitutions are kept too.
+    pub fn lookup_cmd(&self, name: &str, path: &str) -> Option<PathBuf> {
+        self.hash.borrow_mut().lookup(name, path)
+    }
+
+    pub fn peek_cmd(&self, name: &str) -> Option<PathBuf> {
+        self.hash.borrow().peek(name, &self.path())
+    }
+
+    pub fn hash_cmd(&self, name: &str) -> Option<PathBuf> {
+        let path = self.path();
+        self.hash.borrow_mut().add(name, &path)
+    }
+
+    pub fn forget_cmd(&self, name: &str) -> bool {
+        self.hash.borrow_mut().remove(name)
+    }
+
+    pub fn clear_hash(&self) {
+        self.hash.borrow_mut().clear();
+    }
+
+    pub fn hashed(&self) -> Vec<(String, PathBuf, usize)> {
+        let path = self.path();
+        self.hash.borrow_mut().entries(&path)
+    }
+
+    pub fn path(&self) -> String {
+        self.env().get("PATH").cloned().unwrap_or_default()
+    }
+
+    pub fn aliases(&self) -> &HashMap<String, Vec<String>> {
+        self.aliases.aliases()
+    }
+
+    pub fn abbrs(&self) -> &HashMap<String, String> {
+        self.aliases.abbrs()
+    }
+
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    pub fn options_mut(&mut self) -> &mut Options {
+        &mut self.options
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    pub fn dirs_mut(&mut self) -> &mut Vec<PathBuf> {
+        &mut self.dirs
+    }
+
+    pub fn location(&self) -> Option<String> {
+        self.location.as_ref().map(Location::to_string)
+    }
+
+    pub fn source_location(&self) -> Option<Location> {
+        self.location.clone()
+    }
+
+    pub fn set_location(&mut self, location: Option<Location>) {
+        self.location = location;
+    }
+
+    pub fn line(&self) -> usize {
+        self.location.as_ref().map_or(1, |location| location.line)
+    }
+
+    pub fn set_line(&mut self, line: usize) {
+        if let Some(location) = &mut self.location {
+            location.line = line;
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    pub fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    pub fn hooked(&self) -> bool {
+        self.hooked
+    }
+
+    pub fn set_hooked(&mut self, hooked: bool) {
+        self.hooked = hooked;
+    }
+
     pub fn mark(&mut self) {
         self.vars.mark();
     }
@@ -0,0 +1,83 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub errexit: bool,
+    pub nounset: bool,
+    pub pipefail: bool,
+    pub xtrace: bool,
+    pub noclobber: bool,
+    // Accepted for compatibility, as words are never expanded as paths.
+    pub noglob: bool,
+    pub notify: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            errexit: false,
+            nounset: true,
+            pipefail: false,
+            xtrace: false,
+            noclobber: false,
+            noglob: false,
+            notify: true,
+        }
+    }
+}
+
+impl Options {
+    pub fn set<T: AsRef<str>>(&mut self, name: T, on: bool) -> anyhow::Result<()> {
+        let opt = match name.as_ref() {
+            "errexit" | "e" => &mut self.errexit,
+            "nounset" | "u" => &mut self.nounset,
+            "pipefail" => &mut self.pipefail,
+            "xtrace" | "x" => &mut self.xtrace,
+            "noclobber" | "C" => &mut self.noclobber,
+            "noglob" | "f" => &mut self.noglob,
+            "notify" | "b" => &mut self.notify,
+            name => anyhow::bail!("Unknown option: \"{}\"", name),
+        };
+        *opt = on;
+        Ok(())
+    }
+
+    pub fn apply<T: AsRef<str>>(&mut self, args: &[T]) -> anyhow::Result<usize> {
+        let mut i = 0;
+        while let Some(arg) = args.get(i).map(|arg| arg.as_ref()) {
+            let (on, names) = match arg.split_at(arg.len().min(1)) {
+                ("-", "-") => return Ok(i + 1),
+                ("-", long) if long.starts_with('-') => {
+                    anyhow::bail!("Unknown option: \"{}\"", arg)
+                }
+                ("-", names) if !names.is_empty() => (true, names),
+                ("+", names) if !names.is_empty() => (false, names),
+                _ => break,
+            };
+
+            if names == "o" {
+                i += 1;
+                match args.get(i) {
+                    Some(name) => self.set(name, on)?,
+                    None => anyhow::bail!("Specify the option name."),
+                }
+            } else {
+                for name in names.chars() {
+                    self.set(name.to_string(), on)?;
+                }
+            }
+            i += 1;
+        }
+        Ok(i)
+    }
+
+    pub fn list(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("errexit", self.errexit),
+            ("nounset", self.nounset),
+            ("pipefail", self.pipefail),
+            ("xtrace", self.xtrace),
+            ("noclobber", self.noclobber),
+            ("noglob", self.noglob),
+            ("notify", self.notify),
+        ]
+    }
+}
@@ -1,16 +1,20 @@
-use crate::eval::Block;
+use crate::eval::Proc;
 use std::collections::HashMap;
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
-pub struct Procs(HashMap<String, Block>);
+pub struct Procs(HashMap<String, Proc>);
 
 impl Procs {
-    pub fn push<T: Into<String>>(&mut self, name: T, block: Block) {
+    pub fn push<T: Into<String>>(&mut self, name: T, proc: Proc) {
         let name = name.into();
-        self.0.insert(name, block);
+        self.0.insert(name, proc);
     }
 
-    pub fn get<T: AsRef<str>>(&self, name: T) -> Option<Block> {
+    pub fn get<T: AsRef<str>>(&self, name: T) -> Option<Proc> {
         self.0.get(name.as_ref()).cloned()
     }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
 }
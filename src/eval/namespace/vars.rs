@@ -1,59 +1,237 @@
+use crate::eval::Value;
+use std::collections::HashMap;
 use std::env;
 
-#[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Vars {
-    keys: Vec<String>,
-    offsets: Vec<usize>,
+    scopes: Vec<HashMap<String, Var>>,
+    env: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Var {
+    value: Value,
+    readonly: bool,
+    location: Option<String>,
+}
+
+impl Var {
+    fn new(value: Value) -> Self {
+        Self {
+            value,
+            readonly: false,
+            location: None,
+        }
+    }
+
+    fn assign(&mut self, key: &str, value: Value) -> anyhow::Result<()> {
+        self.check(key)?;
+        self.value = value;
+        Ok(())
+    }
+
+    fn check(&self, key: &str) -> anyhow::Result<()> {
+        if self.readonly {
+            match &self.location {
+                Some(location) => anyhow::bail!(
+                    "Can't assign to the constant \"{}\" declared at {}.",
+                    key,
+                    location
+                ),
+                None => anyhow::bail!("Can't assign to the constant \"{}\".", key),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Vars {
+    fn default() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            env: env::vars().collect(),
+        }
+    }
 }
 
 impl Vars {
     pub fn set_args<T, U, US>(&mut self, name: T, args: US)
     where
         T: AsRef<str>,
-        U: AsRef<str>,
+        U: Into<Value>,
         US: IntoIterator<Item = U>,
     {
         let name = name.as_ref();
-        let args = args.into_iter().collect::<Vec<_>>();
+        let args = args.into_iter().map(Into::into).collect::<Vec<Value>>();
         self.push("#", args.len().to_string());
         self.push("0", name);
         for (i, arg) in args.iter().enumerate() {
-            self.push((i + 1).to_string(), arg);
-        }
-        self.push(
-            "@",
-            args.iter()
-                .map(|arg| arg.as_ref())
-                .collect::<Vec<_>>()
-                .join(" "),
-        );
+            self.push((i + 1).to_string(), arg.clone());
+        }
+        self.push("@", args);
+    }
+
+    pub fn get<T: AsRef<str>>(&self, key: T) -> Option<Value> {
+        let key = key.as_ref();
+        self.find(key)
+            .map(|var| var.value.clone())
+            .or_else(|| self.env.get(key).map(Value::from))
+    }
+
+    pub fn push<T: Into<String>, U: Into<Value>>(&mut self, key: T, value: U) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(key.into(), Var::new(value.into()));
+    }
+
+    pub fn set<T: Into<String>, U: Into<Value>>(&mut self, key: T, value: U) -> anyhow::Result<()> {
+        let key = key.into();
+        let exported = self.env.contains_key(&key);
+        match self.find_mut(&key) {
+            Some(var) => var.assign(&key, value.into()),
+            None if exported => {
+                self.env.insert(key, value.into().to_string());
+                Ok(())
+            }
+            None => {
+                self.push(key, value);
+                Ok(())
+            }
+        }
+    }
+
+    // The shell sets its own globals even when they are read-only.
+    pub fn gpush<T: Into<String>, U: Into<Value>>(&mut self, key: T, value: U) {
+        let value = value.into();
+        self.scopes[0]
+            .entry(key.into())
+            .and_modify(|var| var.value = value.clone())
+            .or_insert_with(|| Var::new(value));
     }
 
-    pub fn push<T: Into<String>, U: AsRef<str>>(&mut self, key: T, value: U) {
+    pub fn export<T: Into<String>>(&mut self, key: T, value: Value) -> anyhow::Result<()> {
         let key = key.into();
-        let value = value.as_ref();
-        let exists = env::var(&key).is_ok();
-        env::set_var(&key, value);
-        if !exists {
-            self.keys.push(key);
+        if let Some(var) = self.find_mut(&key) {
+            var.assign(&key, value.clone())?;
         }
+        self.env.insert(key, value.to_string());
+        Ok(())
     }
 
-    pub fn gpush<T: AsRef<str>, U: AsRef<str>>(&mut self, key: T, value: U) {
+    pub fn constant<T: Into<String>>(
+        &mut self,
+        key: T,
+        value: Value,
+        location: Option<String>,
+    ) -> anyhow::Result<()> {
+        let key = key.into();
+        let scope = self.scopes.last_mut().unwrap();
+        if let Some(var) = scope.get_mut(&key) {
+            var.assign(&key, value.clone())?;
+        }
+        scope.insert(
+            key,
+            Var {
+                value,
+                readonly: true,
+                location,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn readonly<T: AsRef<str>>(
+        &mut self,
+        key: T,
+        location: Option<String>,
+    ) -> anyhow::Result<()> {
         let key = key.as_ref();
-        let value = value.as_ref();
-        env::set_var(key, value);
+        if self.find(key).is_none() {
+            match self.env.get(key).cloned() {
+                Some(value) => self.push(key, value),
+                None => anyhow::bail!("Variable \"{}\" is not defined.", key),
+            }
+        }
+
+        let var = self.find_mut(key).unwrap();
+        if !var.readonly {
+            var.readonly = true;
+            var.location = location;
+        }
+        Ok(())
+    }
+
+    pub fn mark_env(
+        &mut self,
+        pairs: Vec<(String, Value)>,
+    ) -> anyhow::Result<Vec<(String, Option<String>)>> {
+        for (key, _) in pairs.iter() {
+            self.writable(key)?;
+        }
+
+        self.mark();
+        let mut saved = Vec::new();
+        for (key, value) in pairs {
+            let old = self.env.insert(key.clone(), value.to_string());
+            self.push(key.clone(), value);
+            saved.push((key, old));
+        }
+        Ok(saved)
+    }
+
+    pub fn drop_env(&mut self, saved: Vec<(String, Option<String>)>) {
+        self.drop();
+        for (key, old) in saved.into_iter().rev() {
+            match old {
+                Some(value) => self.env.insert(key, value),
+                None => self.env.remove(&key),
+            };
+        }
+    }
+
+    pub fn writable(&self, key: &str) -> anyhow::Result<()> {
+        match self.find(key) {
+            Some(var) => var.check(key),
+            None => Ok(()),
+        }
+    }
+
+    pub fn readonly_names(&self) -> Vec<String> {
+        let mut names = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.iter())
+            .filter(|(_, var)| var.readonly)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
     }
 
     pub fn mark(&mut self) {
-        let offset = self.keys.len();
-        self.offsets.push(offset);
+        self.scopes.push(HashMap::new());
     }
 
     pub fn drop(&mut self) {
-        let offset = self.offsets.pop().unwrap_or(0);
-        for key in self.keys.drain(offset..) {
-            env::remove_var(key);
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
         }
     }
+
+    fn find(&self, key: &str) -> Option<&Var> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(key))
+    }
+
+    fn find_mut(&mut self, key: &str) -> Option<&mut Var> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(key))
+    }
 }
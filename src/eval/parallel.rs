@@ -0,0 +1,173 @@
+extern crate nix;
+
+use super::block::{status, step, State};
+use super::{Block, NameSpace, ShellError, Value};
+use crate::job::{Process, SharedJobs, Status};
+use anyhow::Context;
+use nix::unistd::{dup2, fork, ForkResult};
+use std::fs::OpenOptions;
+use std::io::{self, PipeWriter, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::sync::mpsc;
+use std::thread;
+
+// The output of an iteration is printed as a whole once it has finished, so
+// that the outputs of iterations never mix.
+pub fn run<I>(
+    label: &Option<String>,
+    vars: &[String],
+    items: I,
+    limit: usize,
+    block: &Block,
+    jobs: &SharedJobs,
+    ns: &NameSpace,
+) -> anyhow::Result<(Vec<Status>, State)>
+where
+    I: Iterator<Item = (Value, Value)>,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut statuses = Vec::new();
+    let mut state = State::Normal;
+    let mut running = 0;
+
+    for (i, (key, val)) in items.enumerate() {
+        if running == limit {
+            running -= 1;
+            if finish(rx.recv()?, label, &mut statuses, &mut state, jobs)? {
+                break;
+            }
+        }
+
+        let mut ns = ns.clone();
+        match vars {
+            [k, v] => {
+                ns.push_var(k, key);
+                ns.push_var(v, val);
+            }
+            [var] => ns.push_var(var, val),
+            _ => unreachable!(),
+        }
+
+        let (mut out, out_tx) = io::pipe()?;
+        let (mut ctl, ctl_tx) = io::pipe()?;
+        let pid = spawn(block, out_tx, ctl_tx, jobs, &mut ns)?;
+        let id = jobs.with(|jobs| Ok(jobs.new_task(pid)))?;
+        statuses.push(None);
+        running += 1;
+
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let mut jump = String::new();
+            out.read_to_end(&mut output).ok();
+            ctl.read_to_string(&mut jump).ok();
+            let status = Process::from(pid).wait();
+            tx.send((i, id, output, jump, status)).ok();
+        });
+    }
+
+    for _ in 0..running {
+        finish(rx.recv()?, label, &mut statuses, &mut state, jobs)?;
+    }
+
+    Ok((statuses.into_iter().flatten().collect(), state))
+}
+
+type Finished = (usize, usize, Vec<u8>, String, anyhow::Result<Status>);
+
+fn finish(
+    (i, id, output, jump, status): Finished,
+    label: &Option<String>,
+    statuses: &mut [Option<Status>],
+    state: &mut State,
+    jobs: &SharedJobs,
+) -> anyhow::Result<bool> {
+    jobs.with(|jobs| {
+        jobs.finish_task(id);
+        Ok(())
+    })?;
+
+    let mut stdout = io::stdout();
+    stdout.write_all(&output)?;
+    stdout.flush()?;
+    statuses[i] = Some(status?);
+
+    let jump = match jump.split_once(' ') {
+        Some(("break", target)) => {
+            State::Breaked(Some(target).filter(|t| !t.is_empty()).map(String::from))
+        }
+        Some(("continue", target)) => {
+            State::Continued(Some(target).filter(|t| !t.is_empty()).map(String::from))
+        }
+        _ => State::Normal,
+    };
+    Ok(match step(jump, label) {
+        Ok(next) => !next,
+        Err(outer) => {
+            if *state == State::Normal {
+                *state = outer;
+            }
+            true
+        }
+    })
+}
+
+fn spawn(
+    block: &Block,
+    out: PipeWriter,
+    ctl: PipeWriter,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+) -> anyhow::Result<i32> {
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+
+    // The child only has this thread, so a lock held by another thread at the
+    // time of the fork would never be released there. The locks the evaluator
+    // takes are held across the fork instead, and released on both sides.
+    let forked = jobs.with(|_| {
+        let stdout = io::stdout().lock();
+        let stderr = io::stderr().lock();
+        let res = unsafe { fork() }.context("Failed to fork the shell.");
+        drop((stdout, stderr));
+        res
+    })?;
+
+    match forked {
+        ForkResult::Parent { child } => Ok(child.as_raw()),
+        ForkResult::Child => {
+            let code = match iteration(block, out, ctl, jobs, ns) {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ShellError::from_anyhow(&e, ns.location()).status
+                }
+            };
+            io::stdout().flush().ok();
+            std::process::exit(code);
+        }
+    }
+}
+
+fn iteration(
+    block: &Block,
+    out: PipeWriter,
+    mut ctl: PipeWriter,
+    jobs: &SharedJobs,
+    ns: &mut NameSpace,
+) -> anyhow::Result<i32> {
+    let null = OpenOptions::new().read(true).open("/dev/null")?;
+    dup2(null.as_raw_fd(), 0)?;
+    dup2(out.as_raw_fd(), 1)?;
+    dup2(out.as_raw_fd(), 2)?;
+    drop(out);
+
+    let (jump, target) = match block.eval_inner(jobs, ns)? {
+        State::Normal => return Ok(status(ns)),
+        State::Breaked(target) => ("break", target),
+        State::Continued(target) => ("continue", target),
+        State::Returned(_) => anyhow::bail!("\"return\" can't be used in a parallel loop."),
+    };
+    write!(ctl, "{} {}", jump, target.unwrap_or_default())?;
+    Ok(status(ns))
+}
@@ -0,0 +1,205 @@
+use super::{Block, Location, NameSpace, Value};
+use crate::job::SharedJobs;
+use crate::parse::{Signature, Type};
+use anyhow::Context;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proc {
+    sig: Option<Signature>,
+    body: Box<Block>,
+    origin: Option<Location>,
+}
+
+impl Proc {
+    pub(super) fn write(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        if let Some(sig) = &self.sig {
+            write!(f, "{}", sig)?;
+        }
+        write!(f, " ")?;
+        self.body.write_body(f, depth)
+    }
+
+    pub fn describe(&self, name: &str) -> String {
+        Block::Proc(String::from(name), self.clone()).to_string()
+    }
+
+    pub fn new(sig: Option<Signature>, body: Block) -> Self {
+        Self {
+            sig,
+            body: Box::new(body),
+            origin: None,
+        }
+    }
+
+    pub fn defined_at(&self, origin: Option<Location>) -> Self {
+        Self {
+            origin,
+            ..self.clone()
+        }
+    }
+
+    pub fn call(
+        &self,
+        name: &str,
+        args: Vec<Value>,
+        jobs: &SharedJobs,
+        ns: &mut NameSpace,
+    ) -> anyhow::Result<Option<Value>> {
+        let binds = match &self.sig {
+            Some(sig) if help_asked(&args) => {
+                println!("{}", help(name, sig, jobs, ns));
+                return Ok(None);
+            }
+            Some(sig) => bind(name, sig, &args, jobs, ns)?,
+            None => Vec::new(),
+        };
+
+        let options = ns.options();
+        let location = ns.source_location();
+        ns.enter();
+        ns.mark();
+        ns.set_args(name, args);
+        for (key, val) in binds {
+            ns.push_var(key, val);
+        }
+        if self.origin.is_some() {
+            ns.set_location(self.origin.clone());
+        }
+        let res = self.body.eval_proc(jobs, ns);
+        ns.set_location(location);
+        ns.drop();
+        ns.leave();
+        *ns.options_mut() = options;
+        res
+    }
+}
+
+fn bind(
+    name: &str,
+    sig: &Signature,
+    args: &[Value],
+    jobs: &SharedJobs,
+    ns: &NameSpace,
+) -> anyhow::Result<Vec<(String, Value)>> {
+    let mut flags = Vec::new();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let key = match arg {
+            Value::Str(s) if s == "--" => {
+                positional.extend(args.by_ref().cloned());
+                break;
+            }
+            Value::Str(s) if s.starts_with("--") => &s[2..],
+            _ => {
+                positional.push(arg.clone());
+                continue;
+            }
+        };
+
+        let (key, val) = match key.split_once('=') {
+            Some((key, val)) => (key, Some(Value::from(val))),
+            None => (key, None),
+        };
+        let param = sig
+            .0
+            .iter()
+            .find(|param| param.flag && param.name == key)
+            .with_context(|| format!("{}: Unknown flag \"--{}\".", name, key))?;
+        let val = match val {
+            Some(val) => val,
+            None if param.ty == Type::Bool => Value::Bool(true),
+            None => args
+                .next()
+                .cloned()
+                .with_context(|| format!("{}: The flag \"--{}\" requires a value.", name, key))?,
+        };
+        flags.push((key, val));
+    }
+
+    let mut positional = positional.into_iter();
+    let mut res = Vec::new();
+    for param in sig.0.iter() {
+        let val = if param.flag {
+            flags
+                .iter()
+                .rev()
+                .find(|(key, _)| *key == param.name)
+                .map(|(_, val)| val.clone())
+        } else {
+            positional.next()
+        };
+
+        let val = match (val, &param.default) {
+            (Some(val), _) => val,
+            (None, Some(default)) => default.eval_value(jobs, ns)?,
+            (None, None) if param.flag && param.ty == Type::Bool => Value::Bool(false),
+            (None, None) => anyhow::bail!("{}: Missing the argument \"{}\".", name, param.name),
+        };
+        let val = match val.cast(param.ty) {
+            Ok(val) => val,
+            Err(e) => anyhow::bail!("{}: Invalid argument \"{}\": {}", name, param.name, e),
+        };
+        res.push((param.name.replace('-', "_"), val));
+    }
+
+    let rest = positional.count();
+    if rest != 0 {
+        anyhow::bail!("{}: Too many arguments ({} unexpected).", name, rest);
+    }
+
+    Ok(res)
+}
+
+fn help_asked(args: &[Value]) -> bool {
+    args.iter()
+        .take_while(|arg| **arg != Value::from("--"))
+        .any(|arg| *arg == Value::from("--help"))
+}
+
+fn help(name: &str, sig: &Signature, jobs: &SharedJobs, ns: &NameSpace) -> String {
+    let usage = sig
+        .0
+        .iter()
+        .map(|param| match (param.flag, &param.default, param.ty) {
+            (true, _, Type::Bool) => format!("[--{}]", param.name),
+            (true, _, _) => format!("[--{} <{}>]", param.name, param.name),
+            (false, Some(_), _) => format!("[<{}>]", param.name),
+            (false, None, _) => format!("<{}>", param.name),
+        })
+        .collect::<Vec<_>>();
+
+    let names = sig
+        .0
+        .iter()
+        .map(|param| match param.flag {
+            true => format!("--{}", param.name),
+            false => param.name.clone(),
+        })
+        .collect::<Vec<_>>();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+    let mut lines = vec![
+        format!("Usage: {} {}", name, usage.join(" ")),
+        String::new(),
+        String::from("Parameters:"),
+    ];
+    for (param, name) in sig.0.iter().zip(names.iter()) {
+        let default = match &param.default {
+            Some(default) => match default.eval(jobs, ns) {
+                Ok(s) => format!(" (default: {})", s),
+                Err(_) => String::from(" (default: <error>)"),
+            },
+            None => String::new(),
+        };
+        lines.push(format!(
+            "  {:width$}  {}{}",
+            name,
+            param.ty,
+            default,
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
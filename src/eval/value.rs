@@ -0,0 +1,373 @@
+use crate::parse::Type;
+use anyhow::Context;
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Str(String),
+    Int(i64),
+    Float(f64),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Self::Str(String::new())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Str(s) => write!(f, "{}", s),
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Float(x) => write!(f, "{}", x),
+            Self::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            Self::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, val)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, val)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::Str(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::Str(String::from(s))
+    }
+}
+
+impl From<&String> for Value {
+    fn from(s: &String) -> Self {
+        Self::Str(s.clone())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Self::Float(x)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Self::List(items)
+    }
+}
+
+impl Value {
+    pub fn from_text<T: AsRef<str>>(text: T) -> Self {
+        Self::List(text.as_ref().lines().map(Self::from).collect())
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "bool",
+            Self::Str(_) => "str",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::List(_) => "list",
+            Self::Map(_) => "map",
+        }
+    }
+
+    pub fn cast(self, ty: Type) -> anyhow::Result<Self> {
+        let res = match (ty, &self) {
+            (Type::Any, _) => Some(self.clone()),
+            (Type::Str, Self::List(_)) | (Type::Str, Self::Map(_)) => None,
+            (Type::Str, val) => Some(Self::Str(val.to_string())),
+            (Type::Int, Self::Int(_))
+            | (Type::Float, Self::Float(_))
+            | (Type::Bool, Self::Bool(_))
+            | (Type::List, Self::List(_))
+            | (Type::Map, Self::Map(_)) => Some(self.clone()),
+            (Type::Int, Self::Str(s)) => s.parse().ok().map(Self::Int),
+            (Type::Float, Self::Int(i)) => Some(Self::Float(*i as f64)),
+            (Type::Float, Self::Str(s)) => s.parse().ok().map(Self::Float),
+            (Type::Bool, Self::Str(s)) => match s.as_str() {
+                "true" => Some(Self::Bool(true)),
+                "false" => Some(Self::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        res.with_context(|| format!("Expected {}, found {} \"{}\".", ty, self.type_name(), self))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::List(items) => items.len(),
+            Self::Map(entries) => entries.len(),
+            s => s.to_string().chars().count(),
+        }
+    }
+
+    pub fn index(&self, idx: isize) -> anyhow::Result<Self> {
+        let i = self.offset(idx).filter(|i| *i < self.len());
+        let i = match (self, i) {
+            (Self::Map(_), _) => anyhow::bail!("Can't index a map, use the field access instead."),
+            (_, Some(i)) => i,
+            (_, None) => anyhow::bail!("Index {} is out of range (length: {}).", idx, self.len()),
+        };
+
+        Ok(match self {
+            Self::List(items) => items[i].clone(),
+            s => Self::Str(s.to_string().chars().nth(i).unwrap().to_string()),
+        })
+    }
+
+    pub fn slice(&self, start: Option<isize>, end: Option<isize>) -> anyhow::Result<Self> {
+        if let Self::Map(_) = self {
+            anyhow::bail!("Can't slice a map.");
+        }
+
+        let len = self.len();
+        let start = start
+            .map_or(Some(0), |i| self.offset(i))
+            .unwrap_or(0)
+            .min(len);
+        let end = end
+            .map_or(Some(len), |i| self.offset(i))
+            .unwrap_or(0)
+            .min(len);
+        let end = end.max(start);
+
+        Ok(match self {
+            Self::List(items) => Self::List(items[start..end].to_vec()),
+            s => Self::Str(
+                s.to_string()
+                    .chars()
+                    .skip(start)
+                    .take(end - start)
+                    .collect(),
+            ),
+        })
+    }
+
+    pub fn field<T: AsRef<str>>(&self, key: T) -> anyhow::Result<Self> {
+        let key = key.as_ref();
+        match self {
+            Self::Map(entries) => match entries.iter().find(|(k, _)| k == key) {
+                Some((_, val)) => Ok(val.clone()),
+                None => anyhow::bail!("The field \"{}\" does not exist.", key),
+            },
+            _ => anyhow::bail!("Can't access the field \"{}\" of a non-map value.", key),
+        }
+    }
+
+    pub fn insert<T: Into<String>>(&mut self, key: T, val: Self) -> anyhow::Result<()> {
+        let key = key.into();
+        match self {
+            Self::Map(entries) => {
+                match entries.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, v)) => *v = val,
+                    None => entries.push((key, val)),
+                }
+                Ok(())
+            }
+            _ => anyhow::bail!("Can't set the field \"{}\" of a non-map value.", key),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<Self> {
+        match self {
+            Self::Map(entries) => entries.iter().map(|(k, _)| Self::from(k)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn append(&mut self, other: Self) -> anyhow::Result<()> {
+        match (self, other) {
+            (Self::List(items), Self::List(others)) => items.extend(others),
+            (Self::List(items), other) => items.push(other),
+            (map @ Self::Map(_), Self::Map(others)) => {
+                for (key, val) in others {
+                    map.insert(key, val)?;
+                }
+            }
+            (Self::Map(_), _) => anyhow::bail!("Only a map can be merged into a map."),
+            (s, other) => *s = Self::Str(format!("{}{}", s, other)),
+        }
+        Ok(())
+    }
+
+    pub fn compare(&self, other: &Self) -> Ordering {
+        match (self.as_number(), other.as_number()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            Self::List(items) if !items.is_empty() && items.iter().all(Self::is_map) => {
+                table(items)
+            }
+            Self::List(items) => items
+                .iter()
+                .map(Self::render)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Map(entries) => {
+                let width = entries.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+                entries
+                    .iter()
+                    .map(|(k, v)| format!("{:width$}  {}", k, v, width = width))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            s => s.to_string(),
+        }
+    }
+
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth + 1);
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::Str(s) => out.push_str(&format!("{:?}", s)),
+            Self::Float(x) => out.push_str(&format!("{:?}", x)),
+            Self::List(items) if items.is_empty() => out.push_str("[]"),
+            Self::Map(entries) if entries.is_empty() => out.push_str("{}"),
+            Self::List(items) => {
+                out.push_str("[\n");
+                for item in items {
+                    out.push_str(&indent);
+                    item.write_pretty(out, depth + 1);
+                    out.push_str(",\n");
+                }
+                out.push_str(&indent[4..]);
+                out.push(']');
+            }
+            Self::Map(entries) => {
+                out.push_str("{\n");
+                for (key, val) in entries {
+                    out.push_str(&format!("{}{}: ", indent, key));
+                    val.write_pretty(out, depth + 1);
+                    out.push_str(",\n");
+                }
+                out.push_str(&indent[4..]);
+                out.push('}');
+            }
+            s => out.push_str(&s.to_string()),
+        }
+    }
+
+    fn is_map(&self) -> bool {
+        matches!(self, Self::Map(_))
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Int(i) => Some(*i as f64),
+            Self::Float(x) => Some(*x),
+            Self::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn offset(&self, idx: isize) -> Option<usize> {
+        if idx < 0 {
+            self.len().checked_sub(idx.unsigned_abs())
+        } else {
+            Some(idx as usize)
+        }
+    }
+}
+
+fn table(rows: &[Value]) -> String {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        for key in row.keys() {
+            let key = key.to_string();
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|col| row.field(col).map(|v| v.to_string()).unwrap_or_default())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain(Some(col.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let line = |row: &[String]| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![
+        line(&columns),
+        line(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>()),
+    ];
+    lines.extend(cells.iter().map(|row| line(row)));
+    lines.join("\n")
+}
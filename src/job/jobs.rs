@@ -57,19 +57,43 @@ impl SharedJobs {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Jobs(HashMap<usize, Process>);
+pub struct Jobs {
+    procs: HashMap<usize, Process>,
+    notify: bool,
+    pending: Vec<String>,
+}
 
 impl Jobs {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            procs: HashMap::new(),
+            notify: true,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn set_notify(&mut self, notify: bool) {
+        self.notify = notify;
+    }
+
+    pub fn take_pending(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn report(&mut self, msg: String) {
+        if self.notify {
+            eprintln!("\n{}", msg);
+        } else {
+            self.pending.push(msg);
+        }
     }
 
     pub fn new_fg(&mut self, pid: i32) -> anyhow::Result<()> {
-        if self.0.contains_key(&0) {
+        if self.procs.contains_key(&0) {
             anyhow::bail!("The foreground process is already exist.");
         }
 
-        self.0.insert(0, Process::from(pid));
+        self.procs.insert(0, Process::from(pid));
         Ok(())
     }
 
@@ -77,29 +101,40 @@ impl Jobs {
     pub fn new_bg(&mut self, pid: i32) -> anyhow::Result<(usize, i32)> {
         let id = self.get_available_id();
         let proc = Process::from(pid);
-        self.0.insert(id, proc);
+        self.procs.insert(id, proc);
         Ok((id, pid))
     }
 
+    // Tasks are waited for by the parallel loop itself, so the SIGCHLD hook
+    // doesn't report them.
+    pub fn new_task(&mut self, pid: i32) -> usize {
+        let id = self.get_available_id();
+        let mut proc = Process::from(pid);
+        proc.task = true;
+        self.procs.insert(id, proc);
+        id
+    }
+
+    pub fn finish_task(&mut self, id: usize) {
+        self.procs.remove(&id);
+    }
+
     pub fn wait_fg(&mut self) -> anyhow::Result<Option<Status>> {
-        let res = match self.0.get(&0) {
+        let res = match self.procs.get(&0) {
             Some(proc) => Some(proc.wait()?),
             None => None,
         };
         let res = match res {
             Some(status) if status.stopped() => {
-                let mut proc = self.0.remove(&0).unwrap();
+                let mut proc = self.procs.remove(&0).unwrap();
                 let id = self.get_available_id();
                 eprintln!("\nSuspended: %{} ({})", id, proc.pid());
                 proc.suspended = true;
-                self.0.insert(id, proc);
+                self.procs.insert(id, proc);
                 res
             }
-            Some(status) if status.interrupted() => {
-                anyhow::bail!("Interrupted");
-            }
             Some(_) => {
-                self.0.remove(&0);
+                self.procs.remove(&0);
                 res
             }
             None => None,
@@ -113,39 +148,42 @@ impl Jobs {
             None => return Ok(()),
         };
 
-        if self.0.get(&0).map(|proc| proc.pid()) == Some(pid) {
+        if self.procs.get(&0).map(|proc| proc.pid()) == Some(pid) {
             return Ok(());
         }
 
-        let id = match self.from_pid(pid) {
-            Some(id) => id,
-            None => return Ok(()),
+        let id = match self.id_from_pid(pid) {
+            Some(id) if !self.procs[&id].task => id,
+            _ => return Ok(()),
         };
 
-        let mut proc = self.0.remove(&id).context("Failed to get the process.")?;
+        let mut proc = self
+            .procs
+            .remove(&id)
+            .context("Failed to get the process.")?;
 
         match status {
             s if s.continued() => {
-                eprintln!("\n[Background process %{} ({}) continued]", id, pid);
+                self.report(format!("[Background process %{} ({}) continued]", id, pid));
                 proc.suspended = false;
-                self.0.insert(id, proc);
+                self.procs.insert(id, proc);
             }
             s if s.stopped() => {
-                eprintln!("\n[Background process %{} ({}) stopped]", id, pid);
+                self.report(format!("[Background process %{} ({}) stopped]", id, pid));
                 proc.suspended = true;
-                self.0.insert(id, proc);
+                self.procs.insert(id, proc);
             }
             Status::Signaled(s) => {
-                eprintln!(
-                    "\n[Background process %{} ({}) terminated with signal \"{}\"]",
+                self.report(format!(
+                    "[Background process %{} ({}) terminated with signal \"{}\"]",
                     id, pid, s
-                );
+                ));
             }
             Status::Exited(c) => {
-                eprintln!(
-                    "\n[Background process %{} ({}) exited with code \"{}\"]",
+                self.report(format!(
+                    "[Background process %{} ({}) exited with code \"{}\"]",
                     id, pid, c
-                );
+                ));
             }
         }
 
@@ -162,7 +200,7 @@ impl Jobs {
     }
 
     pub fn interrupt(&mut self, id: usize) -> anyhow::Result<Option<Status>> {
-        let proc = self.0.remove(&id);
+        let proc = self.procs.remove(&id);
         if let Some(proc) = proc {
             proc.interrupt().map(Some)
         } else {
@@ -171,10 +209,10 @@ impl Jobs {
     }
 
     pub fn suspend(&mut self, id: usize) -> anyhow::Result<()> {
-        let proc = self.0.remove(&id);
+        let proc = self.procs.remove(&id);
         if let Some(mut proc) = proc {
             proc.suspend()?;
-            self.0.insert(id, proc);
+            self.procs.insert(id, proc);
         }
 
         Ok(())
@@ -185,32 +223,38 @@ impl Jobs {
             return Ok(());
         }
 
-        if self.0.contains_key(&0) {
+        if self.procs.contains_key(&0) {
             anyhow::bail!("The foreground process is already exist.");
         }
 
-        let mut proc = self.0.remove(&id).context("Can't find such a process.")?;
+        let mut proc = self
+            .procs
+            .remove(&id)
+            .context("Can't find such a process.")?;
         if proc.suspended() {
             proc.restart()?;
         }
 
-        self.0.insert(0, proc);
+        self.procs.insert(0, proc);
         Ok(())
     }
 
-    pub fn from_pid(&self, pid: i32) -> Option<usize> {
-        self.0.iter().find(|(_, v)| v.pid() == pid).map(|(k, _)| *k)
+    pub fn id_from_pid(&self, pid: i32) -> Option<usize> {
+        self.procs
+            .iter()
+            .find(|(_, v)| v.pid() == pid)
+            .map(|(k, _)| *k)
     }
 
     pub fn get_pid(&self, id: &usize) -> anyhow::Result<i32> {
         let proc = self
-            .0
+            .procs
             .get(id)
             .context("Failed to get the process number.")?;
         Ok((*proc).into())
     }
 
     fn get_available_id(&self) -> usize {
-        (1..).find(|i| !self.0.contains_key(&i)).unwrap()
+        (1..).find(|i| !self.procs.contains_key(i)).unwrap()
     }
 }
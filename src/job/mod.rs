@@ -4,7 +4,7 @@ mod jobs;
 mod process;
 mod status;
 
-pub use jobs::{Jobs, SharedJobs};
+pub use jobs::SharedJobs;
 pub use nix::sys::signal::Signal;
 pub use process::Process;
 pub use status::Status;
@@ -11,6 +11,7 @@ use super::{Signal, Status};
 pub struct Process {
     pub(super) pid: Pid,
     pub(super) suspended: bool,
+    pub(super) task: bool,
 }
 
 impl fmt::Display for Process {
@@ -31,13 +32,14 @@ impl From<i32> for Process {
         Self {
             pid: Pid::from_raw(id),
             suspended: false,
+            task: false,
         }
     }
 }
 
-impl Into<i32> for Process {
-    fn into(self) -> i32 {
-        self.pid.as_raw()
+impl From<Process> for i32 {
+    fn from(proc: Process) -> Self {
+        proc.pid.as_raw()
     }
 }
 
@@ -46,13 +48,14 @@ impl From<Pid> for Process {
         Self {
             pid: id,
             suspended: false,
+            task: false,
         }
     }
 }
 
-impl Into<Pid> for Process {
-    fn into(self) -> Pid {
-        self.pid
+impl From<Process> for Pid {
+    fn from(proc: Process) -> Self {
+        proc.pid
     }
 }
 
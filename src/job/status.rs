@@ -17,6 +17,13 @@ impl fmt::Display for Status {
 }
 
 impl Status {
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Exited(code) => *code,
+            Self::Signaled(sig) => 128 + *sig as i32,
+        }
+    }
+
     pub fn stopped(&self) -> bool {
         matches!(
             self,
@@ -6,13 +6,47 @@ mod parse;
 mod session;
 
 fn inner_main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    // `--dump-ast` goes with the shell options but isn't one, so that `set`
+    // doesn't accept it.
+    let mut dump_ast = false;
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "--dump-ast" => {
+                args.remove(i);
+                dump_ast = true;
+            }
+            "-o" | "+o" => i += 2,
+            "--" => break,
+            arg if arg.len() > 1 && arg.starts_with(['-', '+']) => i += 1,
+            _ => break,
+        }
+    }
     let mut namespace = eval::NameSpace::default();
-    let mut session = session::Session::new(session::PromptReader::new())?;
-    session.all(&mut namespace)
+    namespace.push_gvar("status", 0);
+    namespace.push_gvar("signal", "");
+    let used = namespace.options_mut().apply(&args)?;
+
+    match args.get(used) {
+        Some(path) => {
+            let mut session = session::Session::new(session::IOReader::new_file(path)?)?;
+            session.set_dump_ast(dump_ast);
+            session.all_with_args(&mut namespace, path, &args[used + 1..])
+        }
+        None => {
+            let mut session = session::Session::new(session::PromptReader::new())?;
+            session.set_dump_ast(dump_ast);
+            session.all(&mut namespace)
+        }
+    }
 }
 
 fn main() {
     inner_main().unwrap_or_else(|e| {
         eprintln!("{}", e);
+        if let Some(err) = e.downcast_ref::<eval::ShellError>() {
+            std::process::exit(err.status);
+        }
     })
 }
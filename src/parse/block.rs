@@ -1,83 +1,185 @@
-use super::{spaces_line, Command, SpecialStr};
+use super::{blanks, spaces, spaces_line, Command, Pattern, Signature, SpecialStr};
 
 use combine::parser::char;
+use combine::stream::position::SourcePosition;
 use combine::{attempt, choice, many, many1, optional, satisfy, sep_by, Parser, Stream};
-use combine::{sep_end_by, token};
+use combine::{not_followed_by, sep_end_by, token};
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Block {
     Single(Command),
-    Multi(Vec<Self>),
-    If(SpecialStr, Box<Self>, Option<Box<Self>>),
-    Case(SpecialStr, Vec<(Vec<SpecialStr>, Self)>),
-    For(String, SpecialStr, Box<Self>),
-    While(SpecialStr, Box<Self>),
-    Proc(String, Box<Self>),
-    Break,
-    Continue,
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+    Multi(Vec<(usize, Self)>),
+    If(Vec<(SpecialStr, Self)>, Option<Box<Self>>),
+    Case(SpecialStr, Vec<Arm>),
+    For(Option<String>, ForHead, Box<Self>),
+    While(Option<String>, SpecialStr, Box<Self>),
+    Loop(Option<String>, Box<Self>),
+    Proc(String, Option<Signature>, Box<Self>),
+    Break(Option<String>),
+    Continue(Option<String>),
+    Return(Option<SpecialStr>),
+    Try(
+        Box<Self>,
+        Option<(Option<String>, Box<Self>)>,
+        Option<Box<Self>>,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForHead {
+    pub vars: Vec<String>,
+    pub iter: SpecialStr,
+    pub step: Option<SpecialStr>,
+    pub parallel: Option<SpecialStr>,
+}
+
+impl fmt::Display for ForHead {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(limit) = &self.parallel {
+            write!(f, "-j {} ", limit)?;
+        }
+        match self.vars.as_slice() {
+            [key, val] => write!(f, "({}, {})", key, val)?,
+            vars => write!(f, "{}", vars.join(" "))?,
+        }
+        write!(f, " in {}", self.iter)?;
+        if let Some(step) = &self.step {
+            write!(f, " step {}", step)?;
+        }
+        Ok(())
+    }
 }
 
 impl Block {
-    pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+    pub fn parse<I: Stream<Token = char, Position = SourcePosition>>(
+    ) -> impl Parser<I, Output = Self> {
         block()
     }
 
-    fn parse_<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+    fn parse_<I: Stream<Token = char, Position = SourcePosition>>() -> impl Parser<I, Output = Self>
+    {
         spaces_line().with(choice((
-            attempt(char::string("break")).map(|_| Self::Break),
-            attempt(char::string("continue")).map(|_| Self::Continue),
-            proc().map(|(name, block)| Self::Proc(name, block)),
-            while_().map(|(cond, block)| Self::While(cond, block)),
-            for_().map(|(c, iter, block)| Self::For(c, iter, block)),
+            attempt(char::string("break"))
+                .with(jump_label())
+                .map(Self::Break),
+            attempt(char::string("continue"))
+                .with(jump_label())
+                .map(Self::Continue),
+            return_().map(Self::Return),
+            try_().map(|(body, catch, finally)| Self::Try(body, catch, finally)),
+            loop_().map(|(label, block)| Self::Loop(label, block)),
+            proc().map(|(name, sig, block)| Self::Proc(name, sig, block)),
+            while_().map(|(label, cond, block)| Self::While(label, cond, block)),
+            for_().map(|(label, head, block)| Self::For(label, head, block)),
             case().map(|(cond, blocks)| Self::Case(cond, blocks)),
-            if_().map(|(cond, first, second)| Self::If(cond, first, second)),
+            if_().map(|(branches, otherwise)| Self::If(branches, otherwise)),
             multi().map(Self::Multi),
-            Command::parse().map(Self::Single),
+            list(),
         )))
     }
 }
 
 combine::parser! {
     fn block[I]()(I) -> Block
-    where [I: Stream<Token = char>]
+    where [I: Stream<Token = char, Position = SourcePosition>]
     {
         Block::parse_()
     }
 }
 
-fn multi<I: Stream<Token = char>>() -> impl Parser<I, Output = Vec<Block>> {
+fn list<I: Stream<Token = char, Position = SourcePosition>>() -> impl Parser<I, Output = Block> {
+    (
+        pipeline(),
+        many((
+            attempt(char::string("&&").or(char::string("||"))).skip(spaces_line()),
+            pipeline(),
+        )),
+    )
+        .map(|(first, rest): (_, Vec<_>)| {
+            rest.into_iter().fold(first, |lhs, (op, rhs)| match op {
+                "&&" => Block::And(Box::new(lhs), Box::new(rhs)),
+                _ => Block::Or(Box::new(lhs), Box::new(rhs)),
+            })
+        })
+}
+
+fn pipeline<I: Stream<Token = char, Position = SourcePosition>>() -> impl Parser<I, Output = Block>
+{
+    (
+        optional(attempt(token('!').skip(char::space())).skip(spaces())),
+        Command::parse(),
+    )
+        .map(|(not, cmd)| match not {
+            Some(_) => Block::Not(Box::new(Block::Single(cmd))),
+            None => Block::Single(cmd),
+        })
+}
+
+fn multi<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Vec<(usize, Block)>> {
     token('{')
         .skip(spaces_line())
         .with(sep_end_by(
-            Block::parse(),
+            (
+                combine::look_ahead(blanks().with(combine::position()))
+                    .map(|pos: SourcePosition| pos.line as usize),
+                Block::parse().skip(spaces()),
+            ),
             token('\n').or(token(';')).with(spaces_line()),
         ))
         .skip(token('}'))
 }
 
-fn if_<I: Stream<Token = char>>(
-) -> impl Parser<I, Output = (SpecialStr, Box<Block>, Option<Box<Block>>)> {
+type Branch = (SpecialStr, Block);
+
+fn if_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = (Vec<Branch>, Option<Box<Block>>)> {
+    (
+        attempt(char::string("if").skip(char::space())).with(branch()),
+        many(
+            attempt(
+                spaces_line()
+                    .with(choice((
+                        attempt(char::string("elif")),
+                        attempt(
+                            char::string("else")
+                                .skip(spaces_line())
+                                .with(char::string("if")),
+                        ),
+                    )))
+                    .skip(char::space()),
+            )
+            .with(branch()),
+        ),
+        optional(
+            attempt(spaces_line().with(char::string("else")))
+                .with(spaces_line())
+                .with(Block::parse().map(Box::new)),
+        ),
+    )
+        .map(|(first, rest, otherwise): (_, Vec<_>, _)| {
+            let mut branches = vec![first];
+            branches.extend(rest);
+            (branches, otherwise)
+        })
+}
+
+fn branch<I: Stream<Token = char, Position = SourcePosition>>() -> impl Parser<I, Output = Branch> {
     (
-        attempt(char::string("if")),
         spaces_line(),
         SpecialStr::parse(),
         spaces_line(),
-        Block::parse().map(Box::new),
-        spaces_line(),
-        optional(
-            (
-                char::string("else"),
-                spaces_line(),
-                Block::parse().map(Box::new),
-            )
-                .map(|(_, _, line)| line),
-        ),
+        Block::parse(),
     )
-        .map(|(_, _, cond, _, first, _, second)| (cond, first, second))
+        .map(|(_, cond, _, block)| (cond, block))
 }
 
-fn case<I: Stream<Token = char>>(
-) -> impl Parser<I, Output = (SpecialStr, Vec<(Vec<SpecialStr>, Block)>)> {
+fn case<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = (SpecialStr, Vec<Arm>)> {
     (
         attempt(char::string("case")),
         spaces_line(),
@@ -88,53 +190,176 @@ fn case<I: Stream<Token = char>>(
         many(
             (
                 sep_by(
-                    SpecialStr::parse().skip(spaces_line()),
+                    Pattern::parse().skip(spaces_line()),
                     token('|').skip(spaces_line()),
                 ),
+                optional(
+                    attempt(char::string("if").skip(spaces_line()))
+                        .with(SpecialStr::parse())
+                        .skip(spaces_line()),
+                ),
                 char::string("=>"),
                 spaces_line(),
                 Block::parse(),
                 spaces_line(),
             )
-                .map(|(pats, _, _, block, _)| (pats, block)),
+                .map(|(pats, guard, _, _, block, _)| (pats, guard, block)),
         ),
         token('}'),
     )
         .map(|(_, _, cond, _, _, _, blocks, _)| (cond, blocks))
 }
 
-fn for_<I: Stream<Token = char>>() -> impl Parser<I, Output = (String, SpecialStr, Box<Block>)> {
+type Loop<T> = (Option<String>, T, Box<Block>);
+
+fn for_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Loop<ForHead>> {
     (
-        attempt(char::string("for")),
+        attempt((loop_label(), char::string("for"))),
         spaces_line(),
-        many1(satisfy(|c: char| !c.is_whitespace())),
+        optional(
+            attempt(char::string("-j"))
+                .skip(spaces_line())
+                .with(SpecialStr::parse())
+                .skip(spaces_line()),
+        ),
+        for_vars(),
         spaces_line(),
         char::string("in"),
         spaces_line(),
         SpecialStr::parse(),
         spaces_line(),
+        optional(
+            attempt(char::string("step").skip(char::space()))
+                .skip(spaces_line())
+                .with(SpecialStr::parse())
+                .skip(spaces_line()),
+        ),
         Block::parse().map(Box::new),
     )
-        .map(|(_, _, c, _, _, _, iter, _, block)| (c, iter, block))
+        .map(
+            |((label, _), _, parallel, vars, _, _, _, iter, _, step, block)| {
+                let head = ForHead {
+                    vars,
+                    iter,
+                    step,
+                    parallel,
+                };
+                (label, head, block)
+            },
+        )
 }
 
-fn while_<I: Stream<Token = char>>() -> impl Parser<I, Output = (SpecialStr, Box<Block>)> {
+fn for_vars<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Vec<String>> {
+    let name = || many1(satisfy(|c: char| c.is_alphanumeric() || c == '_'));
     (
-        attempt(char::string("while")),
+        token('(').skip(spaces_line()),
+        name().skip(spaces_line()),
+        token(',').skip(spaces_line()),
+        name().skip(spaces_line()),
+        token(')'),
+    )
+        .map(|(_, key, _, val, _)| vec![key, val])
+        .or(many1(satisfy(|c: char| !c.is_whitespace())).map(|name| vec![name]))
+}
+
+fn while_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Loop<SpecialStr>> {
+    (
+        attempt((loop_label(), char::string("while"))),
         spaces_line(),
         SpecialStr::parse(),
         spaces_line(),
         Block::parse().map(Box::new),
     )
-        .map(|(_, _, cond, _, block)| (cond, block))
+        .map(|((label, _), _, cond, _, block)| (label, cond, block))
+}
+
+fn loop_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = (Option<String>, Box<Block>)> {
+    attempt((
+        loop_label(),
+        char::string("loop"),
+        spaces_line(),
+        combine::look_ahead(token('{')),
+    ))
+    .map(|(label, _, _, _)| label)
+    .and(Block::parse().map(Box::new))
+}
+
+fn label<I: Stream<Token = char, Position = SourcePosition>>() -> impl Parser<I, Output = String> {
+    token('\'').with(many1(satisfy(|c: char| c.is_alphanumeric() || c == '_')))
+}
+
+fn loop_label<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Option<String>> {
+    optional(label().skip(token(':')).skip(spaces_line()))
+}
+
+fn jump_label<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Option<String>> {
+    optional(attempt(spaces().with(label()))).skip(spaces())
+}
+
+type Arm = (Vec<Pattern>, Option<SpecialStr>, Block);
+
+type Catch = (Option<String>, Box<Block>);
+
+fn try_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = (Box<Block>, Option<Catch>, Option<Box<Block>>)> {
+    (
+        attempt(
+            char::string("try")
+                .skip(spaces_line())
+                .skip(combine::look_ahead(token('{'))),
+        ),
+        Block::parse().map(Box::new),
+        optional(
+            attempt(spaces_line().with(char::string("catch"))).with(
+                (
+                    spaces_line(),
+                    optional(
+                        many1(satisfy(|c: char| c.is_alphanumeric() || c == '_'))
+                            .skip(spaces_line()),
+                    ),
+                    Block::parse().map(Box::new),
+                )
+                    .map(|(_, var, block)| (var, block)),
+            ),
+        ),
+        optional(
+            attempt(spaces_line().with(char::string("finally")))
+                .with(spaces_line())
+                .with(Block::parse().map(Box::new)),
+        ),
+    )
+        .map(|(_, body, catch, finally)| (body, catch, finally))
+}
+
+fn return_<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = Option<SpecialStr>> {
+    attempt(
+        char::string("return").skip(not_followed_by(satisfy(|c: char| {
+            !c.is_whitespace() && c != ';' && c != '}'
+        }))),
+    )
+    .skip(spaces())
+    .with(optional(SpecialStr::parse()))
+    .skip(spaces())
 }
 
-fn proc<I: Stream<Token = char>>() -> impl Parser<I, Output = (String, Box<Block>)> {
+fn proc<I: Stream<Token = char, Position = SourcePosition>>(
+) -> impl Parser<I, Output = (String, Option<Signature>, Box<Block>)> {
     attempt((
-        many1(satisfy(|c: char| !c.is_whitespace() && c != '{')),
+        many1(satisfy(|c: char| {
+            !c.is_whitespace() && c != '{' && c != '('
+        })),
+        optional(Signature::parse()),
         spaces_line(),
         combine::look_ahead(token('{')),
     ))
-    .map(|(name, _, _)| name)
+    .map(|(name, sig, _, _)| (name, sig))
     .and(multi().map(|blocks| Box::new(Block::Multi(blocks))))
+    .map(|((name, sig), block)| (name, sig, block))
 }
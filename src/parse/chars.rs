@@ -1,7 +1,8 @@
 use combine::parser::char;
 use combine::parser::repeat::skip_until;
-use combine::{satisfy, skip_many, token};
+use combine::{many1, optional, satisfy, skip_many, token, unexpected_any, value};
 use combine::{Parser, Stream};
+use std::str::FromStr;
 
 pub fn spaces<I: Stream<Token = char>>() -> impl Parser<I, Output = ()> {
     token('#')
@@ -16,3 +17,26 @@ pub fn spaces_line<I: Stream<Token = char>>() -> impl Parser<I, Output = ()> {
         .map(|_| ())
         .or(char::spaces())
 }
+
+pub fn blanks<I: Stream<Token = char>>() -> impl Parser<I, Output = ()> {
+    skip_many(
+        token('#')
+            .and(skip_until(token('\n')))
+            .map(|_| ())
+            .or(satisfy(char::is_whitespace).map(|_| ())),
+    )
+}
+
+pub fn int<I, T>() -> impl Parser<I, Output = T>
+where
+    I: Stream<Token = char>,
+    T: FromStr + Clone,
+{
+    (optional(token('-')), many1(char::digit())).then(|(neg, digits): (_, String)| {
+        let sign = if neg.is_some() { "-" } else { "" };
+        match format!("{}{}", sign, digits).parse() {
+            Ok(i) => value(i).left(),
+            Err(_) => unexpected_any("out-of-range integer").right(),
+        }
+    })
+}
@@ -1,9 +1,15 @@
 use super::{spaces, spaces_line, Redirect, SpecialStr};
-use combine::{attempt, eof, optional, sep_end_by, token};
+use combine::error::StreamError;
+use combine::stream::StreamErrorFor;
+use combine::{
+    attempt, eof, many, many1, not_followed_by, optional, satisfy, sep_end_by, token, value,
+};
 use combine::{Parser, Stream};
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Command {
+    pub env: Vec<(String, SpecialStr)>,
     pub name: SpecialStr,
     pub args: Vec<Arg>,
     pub pipe: Option<Box<Command>>,
@@ -13,6 +19,7 @@ pub struct Command {
 impl Command {
     fn empty() -> Self {
         Self {
+            env: Vec::new(),
             name: SpecialStr::new(),
             args: Vec::new(),
             pipe: None,
@@ -27,21 +34,59 @@ impl Command {
     fn parse_<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
         spaces_line().with(
             eof().map(|_| Self::empty()).or((
-                SpecialStr::parse().skip(spaces()),
+                many::<Vec<_>, _, _>(assign()),
+                optional(SpecialStr::parse_name().skip(spaces())),
                 sep_end_by(Arg::parse(), spaces()),
-                optional(token('|').with(Self::parse())),
-                optional(spaces().with(token('&')).skip(spaces())),
+                optional(attempt(token('|').skip(not_followed_by(token('|')))).with(Self::parse())),
+                optional(
+                    attempt(spaces().with(token('&')).skip(not_followed_by(token('&'))))
+                        .skip(spaces()),
+                ),
             )
-                .map(|(name, args, pipe, bg)| Self {
-                    name,
-                    args,
-                    pipe: pipe.map(Box::new),
-                    bg: bg.is_some(),
+                .and_then(|(env, name, args, pipe, bg)| {
+                    let name = match name {
+                        Some(name) => name,
+                        None if !env.is_empty() => SpecialStr::new(),
+                        None => {
+                            return Err(StreamErrorFor::<I>::expected_static_message("command"))
+                        }
+                    };
+                    Ok(Self {
+                        env,
+                        name,
+                        args,
+                        pipe: pipe.map(Box::new),
+                        bg: bg.is_some(),
+                    })
                 })),
         )
     }
 }
 
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>();
+        if self.name != SpecialStr::new() {
+            words.push(self.name.to_string());
+        }
+        write!(f, "{}", words.join(" "))?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        if let Some(pipe) = &self.pipe {
+            write!(f, " | {}", pipe)?;
+        }
+        if self.bg {
+            write!(f, " &")?;
+        }
+        Ok(())
+    }
+}
+
 combine::parser! {
     fn command[I]()(I) -> Command
     where [I: Stream<Token = char>]
@@ -50,17 +95,41 @@ combine::parser! {
     }
 }
 
+fn assign<I: Stream<Token = char>>() -> impl Parser<I, Output = (String, SpecialStr)> {
+    (
+        attempt(
+            (
+                many1(satisfy(|c: char| c.is_ascii_alphanumeric() || c == '_')),
+                token('='),
+            )
+                .map(|(key, _)| key),
+        ),
+        SpecialStr::parse().or(value(SpecialStr::new())),
+    )
+        .skip(spaces())
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Arg {
-    ExpandArg(SpecialStr),
-    Arg(SpecialStr),
+    Expand(SpecialStr),
+    Normal(SpecialStr),
     Redirect(Redirect),
 }
 
 impl Arg {
     pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
         attempt(Redirect::parse().map(Self::Redirect))
-            .or(token('!').with(SpecialStr::parse().map(Self::ExpandArg)))
-            .or(SpecialStr::parse().map(Self::Arg))
+            .or(token('!').with(SpecialStr::parse().map(Self::Expand)))
+            .or(SpecialStr::parse_arg().map(Self::Normal))
+    }
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Expand(s) => write!(f, "!{}", s),
+            Self::Normal(s) => write!(f, "{}", s),
+            Self::Redirect(r) => write!(f, "{}", r),
+        }
     }
 }
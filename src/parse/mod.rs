@@ -3,17 +3,21 @@ extern crate combine;
 mod block;
 mod chars;
 mod command;
+mod pattern;
 mod redirect;
+mod signature;
 mod string;
 
-pub use block::Block;
+pub use block::{Block, ForHead};
 pub use command::{Arg, Command};
+pub use pattern::Pattern;
 pub use redirect::{RedKind, RedTarget, Redirect};
+pub use signature::{Signature, Type};
 pub use string::SpecialStr;
 
-use chars::{spaces, spaces_line};
-use combine::{EasyParser, ParseError};
+use chars::{blanks, int, spaces, spaces_line};
 use combine::stream::position::Stream;
+use combine::{EasyParser, ParseError};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Parsed {
@@ -0,0 +1,120 @@
+extern crate glob;
+extern crate regex;
+
+use super::{int, SpecialStr};
+use crate::eval::{NameSpace, Value};
+use crate::job::SharedJobs;
+use anyhow::Context;
+use combine::parser::char;
+use combine::{attempt, choice, not_followed_by, optional, satisfy, token, value};
+use combine::{Parser, Stream};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    Any,
+    Glob(SpecialStr),
+    Regex(SpecialStr),
+    Range(Option<i64>, Option<i64>, bool),
+}
+
+impl Pattern {
+    pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        choice((
+            attempt(token('_').skip(end())).map(|_| Self::Any),
+            token('~')
+                .with(SpecialStr::parse_pattern())
+                .map(Self::Regex),
+            attempt(range().skip(end())),
+            SpecialStr::parse_pattern().map(Self::Glob),
+        ))
+    }
+
+    pub fn matches(
+        &self,
+        val: &Value,
+        jobs: &SharedJobs,
+        ns: &NameSpace,
+    ) -> anyhow::Result<Option<Vec<(String, Value)>>> {
+        Ok(match self {
+            Self::Any => Some(Vec::new()),
+            Self::Glob(pat) => {
+                let pat = pat.eval(jobs, ns)?;
+                let pat = glob::Pattern::new(&pat)
+                    .with_context(|| format!("Invalid glob pattern: \"{}\"", pat))?;
+                Some(Vec::new()).filter(|_| pat.matches(&val.to_string()))
+            }
+            Self::Regex(pat) => {
+                let pat = pat.eval(jobs, ns)?;
+                let re = regex::Regex::new(&pat)
+                    .with_context(|| format!("Invalid regex: \"{}\"", pat))?;
+                let text = val.to_string();
+                re.captures(&text).map(|caps| {
+                    let groups = caps
+                        .iter()
+                        .map(|group| group.map_or(Value::Null, |m| Value::from(m.as_str())))
+                        .collect::<Vec<_>>();
+                    let mut binds = vec![(String::from("match"), Value::List(groups))];
+                    for name in re.capture_names().flatten() {
+                        let group = caps.name(name);
+                        binds.push((
+                            String::from(name),
+                            group.map_or(Value::Null, |m| Value::from(m.as_str())),
+                        ));
+                    }
+                    binds
+                })
+            }
+            Self::Range(start, end, inclusive) => {
+                let num = match val.clone().cast(super::Type::Float) {
+                    Ok(Value::Float(x)) => x,
+                    _ => return Ok(None),
+                };
+                let above = start.is_none_or(|start| num >= start as f64);
+                let below = match (end, inclusive) {
+                    (Some(end), true) => num <= *end as f64,
+                    (Some(end), false) => num < *end as f64,
+                    (None, _) => true,
+                };
+                Some(Vec::new()).filter(|_| above && below)
+            }
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "_"),
+            Self::Glob(pat) => write!(f, "{}", pat),
+            Self::Regex(pat) => write!(f, "~{}", pat),
+            Self::Range(start, end, inclusive) => {
+                if let Some(start) = start {
+                    write!(f, "{}", start)?;
+                }
+                write!(f, "..")?;
+                if *inclusive {
+                    write!(f, "=")?;
+                }
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn range<I: Stream<Token = char>>() -> impl Parser<I, Output = Pattern> {
+    (
+        optional(int()),
+        char::string(".."),
+        optional(token('=')).map(|eq| eq.is_some()),
+        optional(int()),
+    )
+        .map(|(start, _, inclusive, end)| Pattern::Range(start, end, inclusive))
+}
+
+fn end<I: Stream<Token = char>>() -> impl Parser<I, Output = ()> {
+    not_followed_by(satisfy(|c: char| !c.is_whitespace() && c != '|').map(|_| "")).with(value(()))
+}
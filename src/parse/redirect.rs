@@ -1,6 +1,7 @@
 use super::{spaces, SpecialStr};
 use combine::{choice, one_of, token, value};
 use combine::{Parser, Stream};
+use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Redirect {
@@ -84,3 +85,34 @@ impl RedKind {
         ))
     }
 }
+
+impl fmt::Display for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.kind {
+            RedKind::AppendStdout => ">>",
+            RedKind::OverwriteStdout => ">",
+            RedKind::AppendStderr => "2>>",
+            RedKind::OverwriteStderr => "2>",
+            RedKind::AppendBoth => "&>>",
+            RedKind::OverwriteBoth => "&>",
+            RedKind::Stdin => "<",
+            RedKind::HereDoc => "<<",
+        };
+        match &self.target {
+            RedTarget::Other(s) => write!(f, "{} {}", op, s),
+            target => write!(f, "{}{}", op, target),
+        }
+    }
+}
+
+impl fmt::Display for RedTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "&0"),
+            Self::Stdout => write!(f, "&1"),
+            Self::Stderr => write!(f, "&2"),
+            Self::Null => write!(f, "&!"),
+            Self::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
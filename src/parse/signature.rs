@@ -0,0 +1,135 @@
+use super::{spaces_line, SpecialStr};
+use combine::parser::char;
+use combine::{attempt, many1, optional, satisfy, sep_end_by, token, unexpected_any, value};
+use combine::{Parser, Stream};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature(pub Vec<Param>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub flag: bool,
+    pub ty: Type,
+    pub default: Option<SpecialStr>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Any,
+    Str,
+    Int,
+    Float,
+    Bool,
+    List,
+    Map,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Any => "any",
+            Self::Str => "str",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::List => "list",
+            Self::Map => "map",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Type {
+    pub fn new<T: AsRef<str>>(name: T) -> Option<Self> {
+        Some(match name.as_ref() {
+            "any" => Self::Any,
+            "str" => Self::Str,
+            "int" => Self::Int,
+            "float" => Self::Float,
+            "bool" => Self::Bool,
+            "list" => Self::List,
+            "map" => Self::Map,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, param) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.flag {
+            write!(f, "--")?;
+        }
+        write!(f, "{}", self.name)?;
+        if self.ty != Type::Any {
+            write!(f, ": {}", self.ty)?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " = {}", default)?;
+        }
+        Ok(())
+    }
+}
+
+impl Signature {
+    pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        token('(')
+            .skip(spaces_line())
+            .with(sep_end_by(
+                Param::parse().skip(spaces_line()),
+                token(',').skip(spaces_line()),
+            ))
+            .skip(token(')'))
+            .map(Self)
+    }
+}
+
+impl Param {
+    pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        (
+            optional(attempt(char::string("--"))),
+            many1(satisfy(|c: char| {
+                c.is_alphanumeric() || c == '_' || c == '-'
+            })),
+            spaces_line(),
+            optional(
+                token(':')
+                    .skip(spaces_line())
+                    .with(type_())
+                    .skip(spaces_line()),
+            ),
+            optional(
+                token('=')
+                    .skip(spaces_line())
+                    .with(SpecialStr::parse_until(",")),
+            ),
+        )
+            .map(|(flag, name, _, ty, default)| Self {
+                name,
+                flag: flag.is_some(),
+                ty: ty.unwrap_or(Type::Any),
+                default,
+            })
+    }
+}
+
+fn type_<I: Stream<Token = char>>() -> impl Parser<I, Output = Type> {
+    many1(satisfy(|c: char| c.is_alphanumeric())).then(|name: String| match Type::new(name) {
+        Some(ty) => value(ty).left(),
+        None => unexpected_any("type").right(),
+    })
+}
@@ -1,11 +1,15 @@
 extern crate unindent;
 
-use super::Command;
+use super::{int, spaces, spaces_line, Command};
+use crate::eval::{NameSpace, Value};
+use crate::job::SharedJobs;
 use combine::parser::char;
 use combine::{
-    any, attempt, choice, count_min_max, many, many1, one_of, parser, satisfy, token, value,
+    any, attempt, choice, count_min_max, eof, look_ahead, many, many1, not_followed_by, one_of,
+    optional, parser, satisfy, sep_end_by, token, unexpected_any, value,
 };
 use combine::{ParseError, Parser, Stream};
+use std::fmt;
 use unindent::unindent;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -14,11 +18,27 @@ pub struct SpecialStr(Vec<StrKind>);
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum StrKind {
     String(String),
-    Var(String),
-    Cmd(Command),
+    Var(VarRef),
+    Len(VarRef),
+    List(Vec<SpecialStr>),
+    Map(Vec<(String, SpecialStr)>),
+    Cmd(Command, Vec<Access>),
     Pid(usize),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VarRef {
+    name: String,
+    access: Vec<Access>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Access {
+    Index(isize),
+    Slice(Option<isize>, Option<isize>),
+    Field(String),
+}
+
 impl From<String> for SpecialStr {
     fn from(s: String) -> Self {
         Self(vec![StrKind::String(s)])
@@ -31,51 +51,311 @@ impl SpecialStr {
     }
 
     pub fn parse<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        special_str("", true)
+    }
+
+    pub fn parse_until<I: Stream<Token = char>>(
+        stop: &'static str,
+    ) -> impl Parser<I, Output = Self> {
+        special_str(stop, true)
+    }
+
+    pub fn parse_pattern<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        special_str("", false)
+    }
+
+    // A lone `[` is the test command rather than the start of a list.
+    pub fn parse_name<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        bare('[').or(special_str("", true))
+    }
+
+    // A lone `]` closes the test command.
+    pub fn parse_arg<I: Stream<Token = char>>() -> impl Parser<I, Output = Self> {
+        bare(']').or(special_str("", true))
+    }
+
+    fn parse_<I: Stream<Token = char>>(
+        stop: &'static str,
+        lists: bool,
+    ) -> impl Parser<I, Output = Self> {
         choice((
             attempt(raw_unindent()).map(Self::from),
             raw_str().map(|s| Self(vec![StrKind::String(s)])),
             attempt(lit_unindent()),
             lit(),
-            direct(),
+            direct(stop, lists),
+        ))
+    }
+
+    pub fn eval(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<String> {
+        Ok(self.eval_value(jobs, ns)?.to_string())
+    }
+
+    pub fn eval_value(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Value> {
+        if let [kind] = self.0.as_slice() {
+            return kind.eval(jobs, ns);
+        }
+
+        Ok(Value::Str(
+            self.0
+                .iter()
+                .map(|kind| Ok(kind.eval(jobs, ns)?.to_string()))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join(""),
         ))
     }
+}
+
+impl fmt::Display for SpecialStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let quoted = self.0.is_empty()
+            || self.0.iter().any(|kind| match kind {
+                StrKind::String(s) => s.is_empty() || s.contains(special),
+                _ => false,
+            });
 
-    pub fn eval(&self, jobs: &crate::job::SharedJobs) -> anyhow::Result<String> {
-        Ok(self
-            .0
-            .iter()
-            .map(|kind| -> anyhow::Result<_> {
-                match kind {
-                    StrKind::String(s) => Ok(s.clone()),
-                    StrKind::Var(key) => Ok(std::env::var(key)?),
-                    StrKind::Cmd(cmd) => Ok(crate::eval::Command::from(cmd.clone())
-                        .output(jobs)?
-                        .trim()
-                        .to_string()),
-                    StrKind::Pid(id) => Ok(jobs.with(|jobs| jobs.get_pid(id))?.to_string()),
+        match self.0.as_slice() {
+            [StrKind::String(s)] if quoted => {
+                write!(f, "'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+            }
+            kinds if quoted => {
+                write!(f, "\"")?;
+                for (i, kind) in kinds.iter().enumerate() {
+                    match kind {
+                        StrKind::String(s) => write!(f, "{}", escape(s))?,
+                        kind => kind.write(f, kinds.get(i + 1))?,
+                    }
                 }
-            })
-            .collect::<Result<Vec<_>, _>>()?
-            .join(""))
+                write!(f, "\"")
+            }
+            kinds => {
+                for (i, kind) in kinds.iter().enumerate() {
+                    kind.write(f, kinds.get(i + 1))?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
-fn direct<I: Stream<Token = char>>() -> impl Parser<I, Output = SpecialStr> {
-    many1(choice((
-        command().map(StrKind::Cmd),
-        env().map(StrKind::Var),
+fn special(c: char) -> bool {
+    c.is_whitespace() || "#|&;${}()[]\"'\\%".contains(c)
+}
+
+fn escape(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            '\x1b' => res.push_str("\\e"),
+            '\\' | '"' | '$' | '(' | '%' => {
+                res.push('\\');
+                res.push(c);
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+impl StrKind {
+    fn write(&self, f: &mut fmt::Formatter, next: Option<&Self>) -> fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{}", s),
+            Self::Var(var) | Self::Len(var) => {
+                write!(f, "$")?;
+                if let Self::Len(_) = self {
+                    write!(f, "#")?;
+                }
+                let joined = match next {
+                    Some(Self::String(s)) => s.starts_with(|c: char| {
+                        c.is_alphanumeric() || c == '_' || c == '.' || c == '['
+                    }),
+                    _ => false,
+                };
+                if joined && var.access.is_empty() {
+                    write!(f, "{{{}}}", var.name)
+                } else {
+                    write!(f, "{}", var.name)?;
+                    write_access(f, &var.access)
+                }
+            }
+            Self::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, val)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    if key.is_empty()
+                        || key.contains(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+                    {
+                        write!(f, "\"{}\": {}", key, val)?;
+                    } else {
+                        write!(f, "{}: {}", key, val)?;
+                    }
+                }
+                write!(f, "}}")
+            }
+            Self::Cmd(cmd, access) => {
+                write!(f, "({})", cmd)?;
+                write_access(f, access)
+            }
+            Self::Pid(id) => write!(f, "%{}", id),
+        }
+    }
+
+    fn eval(&self, jobs: &SharedJobs, ns: &NameSpace) -> anyhow::Result<Value> {
+        match self {
+            Self::String(s) => Ok(Value::from(s)),
+            Self::Var(var) => var.eval(ns),
+            Self::Len(var) => Ok(Value::Str(var.eval(ns)?.len().to_string())),
+            Self::List(items) => Ok(Value::List(
+                items
+                    .iter()
+                    .map(|item| item.eval_value(jobs, ns))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Self::Map(entries) => Ok(Value::Map(
+                entries
+                    .iter()
+                    .map(|(key, val)| Ok((key.clone(), val.eval_value(jobs, ns)?)))
+                    .collect::<anyhow::Result<_>>()?,
+            )),
+            Self::Cmd(cmd, access) => {
+                let val = crate::eval::Command::from(cmd.clone()).output(jobs, ns)?;
+                apply(val, access)
+            }
+            Self::Pid(id) => Ok(Value::Str(jobs.with(|jobs| jobs.get_pid(id))?.to_string())),
+        }
+    }
+}
+
+impl VarRef {
+    fn eval(&self, ns: &NameSpace) -> anyhow::Result<Value> {
+        let val = match ns.get_var(&self.name) {
+            Some(val) => val,
+            None if !ns.options().nounset => return Ok(Value::default()),
+            None => anyhow::bail!("Variable \"{}\" is not defined.", self.name),
+        };
+        apply(val, &self.access)
+    }
+}
+
+fn write_access(f: &mut fmt::Formatter, access: &[Access]) -> fmt::Result {
+    for access in access {
+        write!(f, "{}", access)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Index(i) => write!(f, "[{}]", i),
+            Self::Slice(start, end) => {
+                write!(f, "[")?;
+                if let Some(start) = start {
+                    write!(f, "{}", start)?;
+                }
+                write!(f, "..")?;
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                write!(f, "]")
+            }
+            Self::Field(key) => write!(f, ".{}", key),
+        }
+    }
+}
+
+// A field access on a value which is not a map is literal text, so that
+// `$name.txt` works as in other shells.
+fn apply(mut val: Value, access: &[Access]) -> anyhow::Result<Value> {
+    for (n, part) in access.iter().enumerate() {
+        val = match part {
+            Access::Index(i) => val.index(*i)?,
+            Access::Slice(start, end) => val.slice(*start, *end)?,
+            Access::Field(key) if matches!(val, Value::Map(_)) => val.field(key)?,
+            Access::Field(_) => {
+                let rest: String = access[n..].iter().map(ToString::to_string).collect();
+                return Ok(Value::Str(format!("{}{}", val, rest)));
+            }
+        };
+    }
+    Ok(val)
+}
+
+combine::parser! {
+    fn special_str[I](stop: &'static str, lists: bool)(I) -> SpecialStr
+    where [I: Stream<Token = char>]
+    {
+        SpecialStr::parse_(stop, *lists)
+    }
+}
+
+fn bare<I: Stream<Token = char>>(c: char) -> impl Parser<I, Output = SpecialStr> {
+    attempt(
+        token(c).skip(look_ahead(
+            satisfy(|d: char| d.is_whitespace() || ";|&)".contains(d))
+                .map(|_| ())
+                .or(eof()),
+        )),
+    )
+    .map(|c: char| SpecialStr::from(c.to_string()))
+}
+
+// A list or map literal must be the whole word, so that `file[1].txt` and
+// `[ab]1` stay text.
+fn direct<I: Stream<Token = char>>(
+    stop: &'static str,
+    lists: bool,
+) -> impl Parser<I, Output = SpecialStr> {
+    let word = many1(choice((
+        command(),
+        env(),
         pid().map(StrKind::Pid),
-        direct_str().map(StrKind::String),
+        direct_str(stop).map(StrKind::String),
     )))
-    .map(SpecialStr)
+    .map(SpecialStr);
+
+    if lists {
+        choice((
+            attempt(list().skip(word_end(stop))).map(StrKind::List),
+            attempt(map().skip(word_end(stop))).map(StrKind::Map),
+        ))
+        .map(|kind| SpecialStr(vec![kind]))
+        .or(word)
+        .left()
+    } else {
+        word.right()
+    }
 }
 
-fn direct_str<I: Stream<Token = char>>() -> impl Parser<I, Output = String> {
-    many1(satisfy(|c: char| {
-        !c.is_whitespace() && "#|&;${}()".chars().all(|d| c != d)
+fn direct_str<I: Stream<Token = char>>(stop: &'static str) -> impl Parser<I, Output = String> {
+    many1(satisfy(move |c: char| {
+        !c.is_whitespace() && "#|&;${}()".chars().chain(stop.chars()).all(|d| c != d)
     }))
 }
 
+fn word_end<I: Stream<Token = char>>(stop: &'static str) -> impl Parser<I, Output = ()> {
+    not_followed_by(
+        satisfy(move |c: char| !c.is_whitespace() && !"|&;)}".contains(c) && !stop.contains(c))
+            .map(|_| ""),
+    )
+}
+
 fn lit_unindent<I: Stream<Token = char>>() -> impl Parser<I, Output = SpecialStr> {
     char::string("\"\"\"")
         .with(parser(|input: &mut I| {
@@ -127,8 +407,8 @@ fn lit_reparse<I: Stream<Token = char>>() -> impl Parser<I, Output = SpecialStr>
     use std::convert::TryFrom;
 
     many1(choice((
-        command().map(StrKind::Cmd),
-        env().map(StrKind::Var),
+        command(),
+        env(),
         pid().map(StrKind::Pid),
         many1(satisfy(|c| c != '$' && c != '(').then(|c| {
             if c == '\\' {
@@ -185,22 +465,95 @@ fn raw_str<I: Stream<Token = char>>() -> impl Parser<I, Output = String> {
         .skip(token('\''))
 }
 
-fn env<I: Stream<Token = char>>() -> impl Parser<I, Output = String> {
+fn env<I: Stream<Token = char>>() -> impl Parser<I, Output = StrKind> {
     token('$').with(
-        token('{')
-            .with(many1(satisfy(|c| c != '}')).skip(token('}')))
-            .or(many1(satisfy(|c: char| {
-                !c.is_whitespace() && c != '"' && c != '\'' && c != ')'
-            }))),
+        attempt(token('#').with(var_ref()))
+            .map(StrKind::Len)
+            .or(var_ref().map(StrKind::Var)),
     )
 }
 
-fn command<I: Stream<Token = char>>() -> impl Parser<I, Output = Command> {
-    token('(').with(Command::parse()).skip(token(')'))
+fn var_ref<I: Stream<Token = char>>() -> impl Parser<I, Output = VarRef> {
+    token('{')
+        .with(many1(satisfy(|c| c != '}')))
+        .skip(token('}'))
+        .map(|name| VarRef {
+            name,
+            access: Vec::new(),
+        })
+        .or((
+            ident().or(one_of("#@".chars()).map(String::from)),
+            many(access()),
+        )
+            .map(|(name, access)| VarRef { name, access }))
+}
+
+fn ident<I: Stream<Token = char>>() -> impl Parser<I, Output = String> {
+    many1(satisfy(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+fn access<I: Stream<Token = char>>() -> impl Parser<I, Output = Access> {
+    attempt(token('.').with(ident()))
+        .map(Access::Field)
+        .or(attempt(index()))
+}
+
+fn index<I: Stream<Token = char>>() -> impl Parser<I, Output = Access> {
+    token('[')
+        .with((
+            optional(int()),
+            optional(char::string("..").with(optional(int()))),
+        ))
+        .skip(token(']'))
+        .then(|range| match range {
+            (start, Some(end)) => value(Access::Slice(start, end)).left(),
+            (Some(i), None) => value(Access::Index(i)).left(),
+            (None, None) => unexpected_any("empty index").right(),
+        })
+}
+
+fn list<I: Stream<Token = char>>() -> impl Parser<I, Output = Vec<SpecialStr>> {
+    token('[')
+        .skip(spaces_line())
+        .with(sep_end_by(SpecialStr::parse_until("]"), spaces_line()))
+        .skip(token(']'))
+}
+
+fn map<I: Stream<Token = char>>() -> impl Parser<I, Output = Vec<(String, SpecialStr)>> {
+    token('{')
+        .skip(spaces_line())
+        .with(sep_end_by(
+            (
+                map_key().skip(spaces()),
+                token(':').skip(spaces_line()),
+                SpecialStr::parse_until(",").skip(spaces()),
+            )
+                .map(|(key, _, val)| (key, val)),
+            one_of(",\n".chars()).skip(spaces_line()),
+        ))
+        .skip(token('}'))
+}
+
+fn map_key<I: Stream<Token = char>>() -> impl Parser<I, Output = String> {
+    choice((
+        raw_str(),
+        token('"')
+            .with(many(satisfy(|c| c != '"')))
+            .skip(token('"')),
+        many1(satisfy(|c: char| {
+            c.is_alphanumeric() || c == '_' || c == '-'
+        })),
+    ))
+}
+
+fn command<I: Stream<Token = char>>() -> impl Parser<I, Output = StrKind> {
+    (
+        token('(').with(Command::parse()).skip(token(')')),
+        many(access()),
+    )
+        .map(|(cmd, access)| StrKind::Cmd(cmd, access))
 }
 
 fn pid<I: Stream<Token = char>>() -> impl Parser<I, Output = usize> {
-    token('%')
-        .with(many1(char::digit()))
-        .map(|id: String| id.parse().unwrap())
+    attempt(token('%').with(many1(char::digit()))).map(|id: String| id.parse().unwrap())
 }
@@ -3,21 +3,32 @@ use std::fs::File;
 use std::io::{BufRead, BufReader, Lines};
 use std::path::Path;
 
-pub struct IOReader<R>(Lines<R>);
+pub struct IOReader<R> {
+    lines: Lines<R>,
+    source: String,
+}
 
 impl<R: BufRead> Reader for IOReader<R> {
     fn next_line(&mut self) -> anyhow::Result<Option<String>> {
-        match self.0.next() {
+        match self.lines.next() {
             Some(line) => Ok(Some(line?)),
             None => Ok(None),
         }
     }
+
+    fn source(&self) -> String {
+        self.source.clone()
+    }
 }
 
 impl IOReader<BufReader<File>> {
     pub fn new_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let source = path.as_ref().display().to_string();
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        Ok(Self(reader.lines()))
+        Ok(Self {
+            lines: reader.lines(),
+            source,
+        })
     }
 }
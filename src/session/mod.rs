@@ -4,13 +4,15 @@ mod prompt;
 pub use io::IOReader;
 pub use prompt::PromptReader;
 
-use crate::eval::{Block, NameSpace};
+use crate::eval::{Block, Location, NameSpace, ShellError, Value};
 use crate::job::SharedJobs;
 use crate::parse::{parse_line, Parsed};
 
 pub struct Session<T> {
     reader: T,
     jobs: SharedJobs,
+    line: usize,
+    dump_ast: bool,
 }
 
 pub trait Reader: Sized {
@@ -18,20 +20,41 @@ pub trait Reader: Sized {
     fn init(&mut self, jobs: &SharedJobs) -> anyhow::Result<()> {
         Ok(())
     }
+    #[allow(unused_variables)]
+    fn sync(&mut self, ns: &NameSpace) {}
     fn next_line(&mut self) -> anyhow::Result<Option<String>>;
     fn more_line(&mut self) -> anyhow::Result<Option<String>> {
         self.next_line()
     }
+    fn source(&self) -> String {
+        String::from("<stdin>")
+    }
 }
 
 impl<T: Reader> Session<T> {
     pub fn new(mut reader: T) -> anyhow::Result<Self> {
         let jobs = SharedJobs::new();
         reader.init(&jobs)?;
-        Ok(Self { reader, jobs })
+        Ok(Self {
+            reader,
+            jobs,
+            line: 0,
+            dump_ast: false,
+        })
+    }
+
+    pub fn set_dump_ast(&mut self, on: bool) {
+        self.dump_ast = on;
     }
 
     pub fn next(&mut self, namespace: &mut NameSpace) -> anyhow::Result<bool> {
+        for msg in self.jobs.with(|jobs| Ok(jobs.take_pending()))? {
+            eprintln!("{}", msg);
+        }
+
+        self.reader.sync(namespace);
+        let start = self.line + 1;
+        self.line += 1;
         let mut line = match self.reader.next_line() {
             Ok(Some(s)) => s,
             Ok(None) => return Ok(false),
@@ -45,6 +68,7 @@ impl<T: Reader> Session<T> {
             match parse_line(line.as_str()) {
                 Ok(Parsed::Complete(cmd)) => break cmd,
                 Ok(Parsed::Yet) => {
+                    self.line += 1;
                     let additional = match self.reader.more_line() {
                         Ok(Some(s)) => s,
                         Ok(None) => return Ok(true),
@@ -64,14 +88,24 @@ impl<T: Reader> Session<T> {
             }
         };
 
-        eprintln!("{:?}", line);
+        if self.dump_ast {
+            eprintln!("{:#?}", line);
+        }
         let block = Block::from(line);
 
-        match block.eval(&self.jobs, namespace) {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("{}", e);
-                return Ok(true);
+        let location = namespace.source_location();
+        namespace.set_location(Some(Location::new(self.reader.source(), start)));
+        let res = block.eval(&self.jobs, namespace);
+        namespace.set_location(location);
+        self.jobs.with(|jobs| {
+            jobs.set_notify(namespace.options().notify);
+            Ok(())
+        })?;
+
+        if let Err(e) = res {
+            match e.downcast_ref::<ShellError>() {
+                Some(err) if err.kind == "exit" => return Err(e),
+                _ => eprintln!("{}", e),
             }
         }
 
@@ -96,13 +130,13 @@ impl<T: Reader> Session<T> {
     ) -> anyhow::Result<()>
     where
         N: AsRef<str>,
-        A: AsRef<str>,
+        A: Into<Value>,
         AS: IntoIterator<Item = A>,
     {
         namespace.mark();
         namespace.set_args(name, args);
-        self.all(namespace)?;
+        let res = self.all(namespace);
         namespace.drop();
-        Ok(())
+        res
     }
 }
@@ -2,23 +2,35 @@ extern crate rustyline;
 extern crate signal_hook;
 
 use super::Reader;
+use crate::eval::NameSpace;
 use crate::job::SharedJobs;
 use anyhow::Context;
 use rustyline::{error::ReadlineError, Editor};
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, EventHandler};
+use rustyline::{KeyEvent, RepeatCount};
 use signal_hook::consts::signal;
 use signal_hook::iterator::Signals;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
 use std::thread;
 
-#[derive(Debug)]
-pub struct PromptReader(Editor<()>);
+pub struct PromptReader {
+    editor: Editor<()>,
+    abbrs: Abbrs,
+}
 
 impl Reader for PromptReader {
     fn init(&mut self, jobs: &SharedJobs) -> anyhow::Result<()> {
         sighook(jobs)
     }
 
+    fn sync(&mut self, ns: &NameSpace) {
+        self.abbrs.set(ns.abbrs());
+    }
+
     fn next_line(&mut self) -> anyhow::Result<Option<String>> {
-        match self.0.readline("$ ") {
+        match self.readline("$ ") {
             Ok(s) => Ok(Some(s)),
             Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
             Err(ReadlineError::Eof) => Ok(None),
@@ -27,7 +39,7 @@ impl Reader for PromptReader {
     }
 
     fn more_line(&mut self) -> anyhow::Result<Option<String>> {
-        match self.0.readline("... ") {
+        match self.readline("... ") {
             Ok(s) => Ok(Some(s)),
             Err(ReadlineError::Eof) => Ok(None),
             Err(e) => Err(e.into()),
@@ -37,12 +49,77 @@ impl Reader for PromptReader {
 
 impl PromptReader {
     pub fn new() -> Self {
-        Self(Editor::new())
+        let mut editor = Editor::new();
+        let abbrs = Abbrs::default();
+        editor.bind_sequence(
+            KeyEvent::from(' '),
+            EventHandler::Conditional(Box::new(abbrs.clone())),
+        );
+        Self { editor, abbrs }
+    }
+
+    fn readline(&mut self, prompt: &str) -> rustyline::Result<String> {
+        let mut initial = (String::new(), String::new());
+        loop {
+            let res = self
+                .editor
+                .readline_with_initial(prompt, (&initial.0, &initial.1));
+            match self.abbrs.take_expanded() {
+                Some(expanded) => {
+                    print!("\x1b[1A\r\x1b[2K");
+                    io::stdout().flush().ok();
+                    initial = expanded;
+                }
+                None => return res,
+            }
+        }
+    }
+}
+
+// Key handlers can't both replace text and move the cursor past it, so the
+// handler interrupts the editor and leaves the expanded line for
+// `PromptReader::readline` to restart with.
+#[derive(Clone, Default)]
+struct Abbrs {
+    abbrs: Arc<Mutex<HashMap<String, String>>>,
+    expanded: Arc<Mutex<Option<(String, String)>>>,
+}
+
+impl Abbrs {
+    fn set(&self, abbrs: &HashMap<String, String>) {
+        if let Ok(mut current) = self.abbrs.lock() {
+            current.clone_from(abbrs);
+        }
+    }
+
+    fn take_expanded(&self) -> Option<(String, String)> {
+        self.expanded.lock().ok()?.take()
+    }
+}
+
+impl ConditionalEventHandler for Abbrs {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        let (head, tail) = ctx.line().split_at(ctx.pos());
+        let start = head.rfind(|c: char| c.is_whitespace()).map_or(0, |i| i + 1);
+        let (before, word) = head.split_at(start);
+        let trimmed = before.trim_end();
+        let command_position = trimmed.is_empty()
+            || ["|", "&&", "||", "!", "{", "("]
+                .iter()
+                .any(|sep| trimmed.ends_with(sep));
+        if word.is_empty() || !command_position {
+            return None;
+        }
+
+        let expansion = self.abbrs.lock().ok()?.get(word)?.clone();
+        let head = format!("{}{} ", before, expansion);
+        *self.expanded.lock().ok()? = Some((head, String::from(tail)));
+        Some(Cmd::Interrupt)
     }
 }
 
 fn sighook(jobs: &SharedJobs) -> anyhow::Result<()> {
-    let mut signals = Signals::new(&[signal::SIGINT, signal::SIGTSTP, signal::SIGCHLD])
+    let mut signals = Signals::new([signal::SIGINT, signal::SIGTSTP, signal::SIGCHLD])
         .context("Failed to initialize signals.")?;
 
     let jobs = jobs.clone();
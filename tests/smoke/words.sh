@@ -0,0 +1,27 @@
+# Brackets and `%` stay literal in words unless they form a list or a job id.
+set -e
+
+[ (echo a]b) = 'a]b' ]
+[ (echo file[1].txt) = 'file[1].txt' ]
+
+pat=abc
+[ "$pat[0-9]" = 'abc[0-9]' ]
+[ $pat[0-9] = 'abc[0-9]' ]
+
+xs=[a b "c d"]
+[ $xs[2] = 'c d' ]
+[ $#xs = 3 ]
+
+case a1 {
+    [ab]1 => true
+    _ => false
+}
+case b {
+    [ab] => true
+    _ => false
+}
+
+[ (printf "%s %d" a 1) = 'a 1' ]
+[ "100%" = 100% ]
+
+echo ok